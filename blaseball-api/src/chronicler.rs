@@ -1,6 +1,6 @@
 use crate::chronicler_schema::{
     ChroniclerGameUpdate, ChroniclerGameUpdatesResponse, ChroniclerItem, ChroniclerPlayer,
-    ChroniclerResponse, ChroniclerTeam,
+    ChroniclerResponse, ChroniclerStadium, ChroniclerTeam,
 };
 use chrono::{DateTime, Utc};
 use futures::{stream, Stream, StreamExt};
@@ -14,6 +14,9 @@ use uuid::Uuid;
 pub struct Chronicler {
     cache: sled::Db,
     client: reqwest::Client,
+    // When set, anything that isn't already cached is treated as the end of
+    // the data instead of being fetched
+    cache_only: bool,
 }
 
 struct ChronPaginationState {
@@ -26,6 +29,16 @@ impl Chronicler {
         Ok(Chronicler {
             cache: sled::open("http_cache/chron/".to_owned())?,
             client: reqwest::Client::new(),
+            cache_only: false,
+        })
+    }
+
+    // Only game updates respect this for now. Entity lookups still go to the
+    // network on a cache miss.
+    pub fn cache_only() -> sled::Result<Self> {
+        Ok(Chronicler {
+            cache_only: true,
+            ..Self::new()?
         })
     }
 
@@ -71,6 +84,20 @@ impl Chronicler {
             .and_then(|items| items.into_iter().next())
     }
 
+    pub async fn stadium(
+        &self,
+        stadium_id: Uuid,
+        at_time: DateTime<Utc>,
+    ) -> Option<ChroniclerItem<ChroniclerStadium>> {
+        let ids = [stadium_id];
+        let mut stream = pin!(self.chronicler_pages("entities", "stadium", Some(&ids), at_time));
+
+        stream
+            .next()
+            .await
+            .and_then(|items| items.into_iter().next())
+    }
+
     pub fn game_updates(&self, start: DateTime<Utc>) -> impl Stream<Item = ChroniclerGameUpdate> + use<'_> {
         self.game_update_pages(start).flat_map(|vec| stream::iter(vec.into_iter()))
     }
@@ -200,6 +227,16 @@ impl Chronicler {
         let cache_key = request.url().to_string();
         let response = match self.cache.get(&cache_key).unwrap() {
             Some(text) => bincode::deserialize(&text).unwrap(),
+            None if self.cache_only => {
+                info!("Game updates page isn't cached; stopping");
+                return (
+                    Vec::new(),
+                    ChronPaginationState {
+                        page: None,
+                        stop: true,
+                    },
+                );
+            }
             None => {
                 info!("Fetching game updates page from network");
 
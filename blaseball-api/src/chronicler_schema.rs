@@ -67,6 +67,22 @@ pub struct ChroniclerTeam {
     pub tournament_wins: i64,
 }
 
+// Only the fields we use so far. Stadiums have a lot of renovation and
+// display state we don't care about.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChroniclerStadium {
+    pub id: Uuid,
+    pub viscosity: f64,
+    pub elongation: f64,
+    pub obtuseness: f64,
+    pub forwardness: f64,
+    pub grandiosity: f64,
+    pub ominousness: f64,
+    pub fortification: f64,
+    pub inconvenience: f64,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChroniclerPlayer {
@@ -90,6 +106,9 @@ pub struct ChroniclerPlayer {
     pub perm_attr: Vec<String>,
     pub seas_attr: Vec<String>,
     pub week_attr: Vec<String>,
+    // Older player versions don't have this
+    #[serde(default)]
+    pub game_attr: Vec<String>,
     pub chasiness: f64,
     pub evolution: i64,
     pub hit_streak: i64,
@@ -0,0 +1,197 @@
+use crate::update_parser;
+use blaseball_api::{Chronicler, ChroniclerGameUpdate};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rocket::futures::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::future;
+use std::pin::pin;
+use thiserror::Error;
+
+// A headless report of which update descriptions the parser can't handle yet.
+// Descriptions are grouped by a template with names and numbers replaced, so
+// each line of the report is (roughly) one missing grammar rule.
+//
+// Usage: desim parser-coverage <start> <end> [--cache-only]
+
+const USAGE: &str = "Usage: desim parser-coverage <start> <end> [--cache-only]";
+
+#[derive(Error, Debug)]
+pub enum CoverageError {
+    #[error("{USAGE}")]
+    InvalidArguments,
+
+    #[error("Couldn't parse time {0:?}: {1}")]
+    InvalidTime(String, chrono::ParseError),
+
+    #[error("Failed to open Chron cache location: {0}")]
+    FailedToOpenChronCache(blaseball_api::Error),
+}
+
+struct CoverageArgs {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    cache_only: bool,
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Utc>, CoverageError> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.to_utc())
+        .map_err(|err| CoverageError::InvalidTime(time.to_string(), err))
+}
+
+fn parse_args(args: &[String]) -> Result<CoverageArgs, CoverageError> {
+    let (flags, positional): (Vec<_>, Vec<_>) = args.iter().partition(|arg| arg.starts_with("--"));
+
+    let [start, end] = positional.as_slice() else {
+        return Err(CoverageError::InvalidArguments);
+    };
+
+    let mut cache_only = false;
+    for flag in flags {
+        match flag.as_str() {
+            "--cache-only" => cache_only = true,
+            _ => return Err(CoverageError::InvalidArguments),
+        }
+    }
+
+    Ok(CoverageArgs {
+        start: parse_time(start)?,
+        end: parse_time(end)?,
+        cache_only,
+    })
+}
+
+// Chron doesn't tell us who the fielders are, but every fielder bats
+// eventually, so collecting names from every update we see (not just the
+// unparsed ones) catches almost everyone
+fn collect_names(names: &mut HashSet<String>, update: &ChroniclerGameUpdate) {
+    let data = &update.data;
+    let update_names = [
+        &data.away_batter_name,
+        &data.home_batter_name,
+        &data.away_pitcher_name,
+        &data.home_pitcher_name,
+        &data.away_team_name,
+        &data.home_team_name,
+        &data.away_team_nickname,
+        &data.home_team_nickname,
+    ]
+    .into_iter()
+    .chain(&data.base_runner_names);
+
+    for name in update_names {
+        if !name.is_empty() && !names.contains(name) {
+            names.insert(name.clone());
+        }
+    }
+}
+
+fn normalize_description(description: &str, names: &[String]) -> String {
+    // `names` is sorted longest first, so a team name is replaced before the
+    // nickname inside it
+    let mut template = description.to_string();
+    for name in names {
+        template = template.replace(name.as_str(), "{name}");
+    }
+
+    let mut normalized = String::with_capacity(template.len());
+    let mut in_number = false;
+    for c in template.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                normalized.push_str("{n}");
+            }
+            in_number = true;
+        } else {
+            normalized.push(c);
+            in_number = false;
+        }
+    }
+    normalized
+}
+
+struct TemplateStats {
+    count: usize,
+    example: String,
+}
+
+pub async fn run(args: &[String]) -> Result<(), CoverageError> {
+    let args = parse_args(args)?;
+    let chron = if args.cache_only {
+        Chronicler::cache_only()
+    } else {
+        Chronicler::new()
+    }
+    .map_err(CoverageError::FailedToOpenChronCache)?;
+
+    let mut game_updates = pin!(chron
+        .game_updates(args.start)
+        .take_while(|update| future::ready(update.timestamp < args.end)));
+
+    let mut names = HashSet::new();
+    let mut unparsed = Vec::new();
+    let mut total = 0;
+    while let Some(update) = game_updates.next().await {
+        total += 1;
+        collect_names(&mut names, &update);
+        if update_parser::parse_update(&update).is_err() {
+            unparsed.push(update.data.last_update);
+        }
+    }
+
+    let names = names
+        .into_iter()
+        .sorted_by_key(|name| std::cmp::Reverse(name.len()))
+        .collect_vec();
+
+    let mut templates: HashMap<String, TemplateStats> = HashMap::new();
+    for description in &unparsed {
+        templates
+            .entry(normalize_description(description, &names))
+            .or_insert_with(|| TemplateStats {
+                count: 0,
+                example: description.clone(),
+            })
+            .count += 1;
+    }
+
+    println!(
+        "Parsed {} of {total} updates ({} unparsed, {} distinct templates)",
+        total - unparsed.len(),
+        unparsed.len(),
+        templates.len(),
+    );
+    for (template, stats) in templates
+        .into_iter()
+        .sorted_by_key(|(template, stats)| (std::cmp::Reverse(stats.count), template.clone()))
+    {
+        println!();
+        println!("{:>8}  {template:?}", stats.count);
+        println!("          e.g. {:?}", stats.example);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_names_and_numbers() {
+        let names = vec![
+            "Jessica Telephone".to_string(),
+            "Nagomi Mcdaniel".to_string(),
+            "York Silk".to_string(),
+        ];
+
+        assert_eq!(
+            normalize_description(
+                "Nagomi Mcdaniel hit a flyout to York Silk.\nJessica Telephone advances to 12th base!",
+                &names
+            ),
+            "{name} hit a flyout to {name}.\n{name} advances to {n}th base!"
+        );
+    }
+}
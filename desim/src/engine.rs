@@ -1,14 +1,25 @@
 use crate::fragments::{CheckRoll, RollStream};
+use crate::game_state::{
+    describe_gap_update, missed_game_start, GameRules, TrackedState, MAX_RECONSTRUCTED_GAP,
+};
+use crate::resync::{ResyncConfig, ResyncSearch, ResyncStatus};
 use crate::rng::Rng;
-use crate::rolls::{rolls_for_update, RollData, RollPurpose, RollUsage};
-use crate::thresholds::Thresholds;
+use crate::rolls::{
+    infer_outcomes, rolls_for_update, scoring_errors, RollData, RollPurpose, RollUsage, RollsError,
+};
+use crate::sim::{ValidationError, ValidationWarning};
+use crate::thresholds::{ThresholdTrace, Thresholds};
+use crate::tick_order::solve_tick_order;
+use crate::update_parser::{ParsedUpdate, ParsedUpdateData};
 use crate::{sim, update_parser};
 use blaseball_api::{Chronicler, ChroniclerGameUpdate};
 use chrono::{DateTime, Utc};
+use itertools::Itertools;
 use serde::Serialize;
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::iter;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -16,7 +27,7 @@ use uuid::Uuid;
 // - ingest a (hopefully chronological) stream of updates
 // - group them into ticks based on chronicler time
 // - match all the rolls that happened that tick with rng outcomes, correcting
-//   for game update order as it does
+//   for game update order as it does (see `tick_order`)
 // - produce <Thing>Context objects describing every game, tick, and roll for
 //   the purpose of rendering
 pub struct Engine {
@@ -26,14 +37,18 @@ pub struct Engine {
     active_games: HashMap<Uuid, sim::Game>,
     // This stores a list of game updates with identical timestamps which have
     // not yet been processed. As soon as an update with a future timestamp is
-    // received, the pending updates are processed. It's TBD what happens to
-    // updates that are in this vec when the event stream ends (the safest thing
-    // is to drop them, if we can't be sure there weren't any more events that
-    // happened on this tick).
+    // received, the pending updates are processed. When the event stream ends,
+    // `finish` processes whatever's left. That assumes the stream ended
+    // between ticks, which is true as long as a fragment's `end_time` isn't
+    // right on top of one.
     pending_updates: Vec<ChroniclerGameUpdate>,
     // Contains an under-construction DayContext (or None if we haven't started yet)
     current_day: Option<DayContext>,
     tick_number: usize,
+    // If this is set, the engine searches for a new Rng position whenever the
+    // rolls stop matching reality
+    resync_config: Option<ResyncConfig>,
+    resync: Option<ResyncSearch>,
 }
 
 #[derive(Debug, Clone, Error)]
@@ -128,6 +143,7 @@ struct RollContext {
     rng_state: String,
     roll: f64,
     usage: RollUsage,
+    threshold_trace: Option<ThresholdTrace>,
     resim_mismatch: Option<ResimMatchContext>,
 }
 
@@ -135,6 +151,10 @@ struct RollContext {
 pub struct GameTickContext {
     game_label: String,
     description: String,
+    // Chron doesn't have this update, so we worked out what it must have been
+    inferred: bool,
+    validation_errors: Vec<ValidationError>,
+    validation_warnings: Vec<ValidationWarning>,
     errors: Vec<String>,
     warnings: Vec<String>,
     rolls: Vec<RollContext>,
@@ -144,6 +164,8 @@ pub struct GameTickContext {
 pub struct TickContext {
     tick_number: usize,
     tick_timestamp: DateTime<Utc>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
     games: Vec<GameTickContext>,
 }
 
@@ -260,19 +282,14 @@ impl RollPurposeMatchContext {
 impl ResimMatchContext {
     pub fn for_roll(roll_data: &RollData, check: &CheckRoll) -> Self {
         match roll_data.usage {
-            RollUsage::Threshold { threshold, passed } => {
-                ResimMatchContext::Threshold {
-                    rolls: FloatMatchContext::from_values(roll_data.roll, check.roll),
-                    purpose: RollPurposeMatchContext::from_values(
-                        &roll_data.purpose,
-                        &check.purpose,
-                    ),
-                    passed: OptionBoolMatchContext::from_values(passed, check.passed()),
-                    // TODO This should have some sort of trace so we know when the threshold
-                    //   was dependent on an earlier roll outcome
-                    threshold: OptionFloatMatchContext::from_values(threshold, check.threshold),
-                }
-            }
+            RollUsage::Threshold {
+                threshold, passed, ..
+            } => ResimMatchContext::Threshold {
+                rolls: FloatMatchContext::from_values(roll_data.roll, check.roll),
+                purpose: RollPurposeMatchContext::from_values(&roll_data.purpose, &check.purpose),
+                passed: OptionBoolMatchContext::from_values(passed, check.passed()),
+                threshold: OptionFloatMatchContext::from_values(threshold, check.threshold),
+            },
             RollUsage::Choice { .. } => ResimMatchContext::Choice {
                 rolls: FloatMatchContext::from_values(roll_data.roll, check.roll),
                 purpose: RollPurposeMatchContext::from_values(&roll_data.purpose, &check.purpose),
@@ -291,13 +308,18 @@ impl RollContext {
             rng_state: roll_data.state_string,
             roll: roll_data.roll,
             usage: roll_data.usage,
+            threshold_trace: roll_data.threshold_trace,
             resim_mismatch,
         }
     }
 }
 
 impl Engine {
-    pub fn new(rng: Rng, check_rolls: Option<RollStream>) -> Engine {
+    pub fn new(
+        rng: Rng,
+        check_rolls: Option<RollStream>,
+        resync_config: Option<ResyncConfig>,
+    ) -> Engine {
         Engine {
             rng,
             check_rolls,
@@ -305,6 +327,8 @@ impl Engine {
             pending_updates: Vec::new(),
             current_day: None,
             tick_number: 0,
+            resync_config,
+            resync: None,
         }
     }
 
@@ -346,15 +370,29 @@ impl Engine {
         }
     }
 
+    // Call this once the event stream ends to get every day that hasn't been
+    // returned yet. Usually that's just the day in progress, but the last
+    // tick can be the first of a new day.
+    pub async fn finish(
+        mut self,
+        th: &Thresholds,
+        chron: &Chronicler,
+    ) -> Result<Vec<DayContext>, EngineFatalError> {
+        let mut days = Vec::new();
+        if !self.pending_updates.is_empty() {
+            let updates_to_process = std::mem::take(&mut self.pending_updates);
+            days.extend(self.tick(updates_to_process, th, chron).await?);
+        }
+        days.extend(self.current_day.take());
+        Ok(days)
+    }
+
     async fn tick(
         &mut self,
         updates: Vec<ChroniclerGameUpdate>,
         th: &Thresholds,
         chron: &Chronicler,
     ) -> Result<Option<DayContext>, EngineFatalError> {
-        // In the future, this will be responsible for figuring out tick order.
-        // For now I'm only feeding it ticks with one event at a time.
-
         let update = updates
             .first()
             .expect("tick() must be called with at least one update");
@@ -413,44 +451,146 @@ impl Engine {
         // can move out of `updates`
         let tick_timestamp = update.timestamp;
 
-        // This is where we will have to figure out event order eventually
-
-        // sigh. i want to use iterator methods for this but i can't figure out
-        // how to please the borrow checker
-        let mut game_updates = Vec::new();
-        game_updates.reserve_exact(updates.len());
-        for update in updates {
-            // Can't use or_insert_with because fetching a game is async
-            let game_update = match self.active_games.entry(update.game_id) {
-                Entry::Occupied(mut entry) => run_game_tick(
-                    entry.get_mut(),
-                    update,
-                    th,
-                    &mut self.rng,
-                    &mut self.check_rolls,
-                )?,
-                Entry::Vacant(entry) => {
-                    // The first few updates of a game can be skipped and nothing bad happens
-                    // (because they don't do any rolls), but if we're starting a game later than
-                    // approximately play count 3 something has gone wrong
-                    assert!(update.data.play_count < 3);
-                    let game_at_tick = sim::Game::from_first_game_update(&update, chron).await;
-                    run_game_tick(
-                        entry.insert(game_at_tick),
-                        update,
-                        th,
-                        &mut self.rng,
-                        &mut self.check_rolls,
-                    )?
+        // Every game has to exist before we can try orderings. Can't use
+        // or_insert_with because fetching a game is async
+        let mut warnings = Vec::new();
+        for update in &updates {
+            if let Entry::Vacant(entry) = self.active_games.entry(update.game_id) {
+                // The first few updates of a game can be skipped and nothing bad happens
+                // (because they don't do any rolls), but if we're starting a game later than
+                // approximately play count 3 we've missed some rolls
+                if update.data.play_count >= 3 {
+                    warnings.push(format!(
+                        "Started following {} @ {} at play count {}, so its earlier rolls are \
+                        missing",
+                        update.data.away_team_nickname,
+                        update.data.home_team_nickname,
+                        update.data.play_count,
+                    ));
                 }
-            };
+                entry.insert(sim::Game::from_first_game_update(update, chron).await);
+            }
+        }
+
+        let tick_order = solve_tick_order(
+            &self.active_games,
+            &updates,
+            th,
+            &self.rng,
+            self.check_rolls.as_ref(),
+        );
 
-            game_updates.push(game_update);
+        let mut errors = Vec::new();
+        if tick_order.contradictions > 0 {
+            errors.push(format!(
+                "No ordering of this tick's {} game updates agrees with every observed outcome. \
+                Using the ordering with the fewest contradictions ({}).",
+                updates.len(),
+                tick_order.contradictions,
+            ));
+        }
+        if tick_order.ambiguous {
+            warnings.push(
+                "More than one ordering of this tick's game updates, or of the updates Chron \
+                missed, agrees with every observed outcome. Using the first one found."
+                    .to_string(),
+            );
+        }
+        if tick_order.gave_up {
+            warnings.push(
+                "Gave up searching for this tick's game order before trying every ordering"
+                    .to_string(),
+            );
+        }
+
+        let mut updates = updates.into_iter().map(Some).collect_vec();
+        let mut game_updates = Vec::with_capacity(updates.len());
+        for update_index in tick_order.order {
+            let update = updates[update_index]
+                .take()
+                .expect("Tick order must use each update exactly once");
+            let game = self
+                .active_games
+                .get_mut(&update.game_id)
+                .expect("Every game in the tick was fetched above");
+
+            let mut resync_warning = None;
+            if let (Some(search), Some(config)) = (&mut self.resync, &self.resync_config) {
+                match search.advance(game, &update, th, self.check_rolls.as_ref(), config) {
+                    ResyncStatus::Searching => {}
+                    ResyncStatus::Found {
+                        offset,
+                        rng,
+                        check_rolls_step,
+                    } => {
+                        resync_warning = Some(format!(
+                            "Resynced the Rng by {offset:+} rolls from where it was when the \
+                            desync was detected"
+                        ));
+                        self.rng = rng;
+                        self.resync = None;
+                        // The check rolls follow the Rng, or they'd disagree
+                        // with every roll from here on
+                        if let Some(check_rolls) = &mut self.check_rolls {
+                            if !check_rolls.step(check_rolls_step) {
+                                return Err(EngineFatalError::RanOutOfCheckRolls);
+                            }
+                        }
+                    }
+                    ResyncStatus::Failed => {
+                        resync_warning = Some(format!(
+                            "Couldn't find a resync point within {} rolls of the desync",
+                            config.window
+                        ));
+                        self.resync = None;
+                    }
+                }
+            }
+
+            game_updates.extend(run_missing_updates(
+                game,
+                &update,
+                th,
+                &mut self.rng,
+                &mut self.check_rolls,
+                tick_order.gap_choices[update_index],
+            )?);
+            let (mut game_tick, sync) =
+                run_game_tick(game, &update, th, &mut self.rng, &mut self.check_rolls)?;
+            game_tick.warnings.extend(resync_warning);
+            game.update_pitchers(&update, chron).await;
+            game.update_lineups(&update, chron).await;
+
+            if let Some(config) = &self.resync_config {
+                // A mismatch while we're already searching is expected, but an
+                // unparsed update throws off every candidate so we start over
+                let should_start = match sync {
+                    GameTickSync::InSync => false,
+                    GameTickSync::Mismatch => self.resync.is_none(),
+                    GameTickSync::Unparsed => true,
+                };
+                if should_start {
+                    game_tick.warnings.push(format!(
+                        "Rolls desynced at this update. Searching {} rolls in each direction \
+                        for a resync.",
+                        config.window
+                    ));
+                    self.resync = Some(ResyncSearch::start(
+                        &self.rng,
+                        self.check_rolls.as_ref(),
+                        config,
+                    ));
+                }
+            }
+
+            game_updates.push(game_tick);
         }
 
         day.ticks.push(TickContext {
             tick_number: self.tick_number,
             tick_timestamp,
+            errors,
+            warnings,
             games: game_updates,
         });
 
@@ -459,60 +599,760 @@ impl Engine {
     }
 }
 
+// Whether our rolls still seem to line up with reality after a game tick
+enum GameTickSync {
+    InSync,
+    // A roll disagreed with an observed outcome or with resim
+    Mismatch,
+    // We couldn't parse the update or work out its rolls, so we don't know how
+    // many rolls it used
+    Unparsed,
+}
+
 // Sadly, this can't be an
 fn run_game_tick(
     game: &mut sim::Game,
-    update: ChroniclerGameUpdate,
+    update: &ChroniclerGameUpdate,
     th: &Thresholds,
     rng: &mut Rng,
     check_rolls: &mut Option<RollStream>,
-) -> Result<GameTickContext, EngineFatalError> {
-    let game_at_tick = game.at_tick(&update);
+) -> Result<(GameTickContext, GameTickSync), EngineFatalError> {
+    let parsed_update = update_parser::parse_update(update);
+    // This has to happen before `at_tick` borrows the game
+    let state_errors = game.track_state(
+        parsed_update.as_ref().ok().map(|parsed| &parsed.data),
+        update,
+    );
+    let game_at_tick = game.at_tick(update);
 
-    let (mut errors, warnings) = game_at_tick.validate(&update);
+    let (validation_errors, validation_warnings) = game_at_tick.validate(update);
+    let mut errors = state_errors;
     let game_label = format!(
         "{} @ {}",
         update.data.away_team_nickname, update.data.home_team_nickname
     );
-    match update_parser::parse_update(&update) {
+    match parsed_update {
         Ok(parsed_update) => {
-            let rolls = rolls_for_update(rng, parsed_update, th, &game_at_tick)
-                .into_iter()
-                .map(|roll_data| {
-                    if let Some(check_rolls) = check_rolls {
-                        // TODO This is the least efficient way to do it
-                        if let Some(check_roll) = check_rolls.pop_front() {
-                            Ok(RollContext::for_roll(roll_data, Some(check_roll)))
-                        } else {
-                            Err(EngineFatalError::RanOutOfCheckRolls)
-                        }
-                    } else {
-                        Ok(RollContext::for_roll(roll_data, None))
-                    }
-                })
-                .collect::<Result<_, _>>()?;
+            if let Some(batter) = parsed_update.data.batter() {
+                let expected_batter = &game_at_tick.batter().player.name;
+                if batter != expected_batter {
+                    errors.push(format!(
+                        "Update says the batter is {batter}, but it should be {expected_batter}"
+                    ));
+                }
+            }
+
+            errors.extend(scoring_errors(&parsed_update.data, &game_at_tick));
+
+            let mut sync = GameTickSync::InSync;
+            // The Rng only moves if the rolls could be worked out
+            let mut rolls_rng = rng.clone();
+            let rolls = match rolls_for_update(&mut rolls_rng, parsed_update, th, &game_at_tick) {
+                Ok(rolls) => {
+                    *rng = rolls_rng;
+                    roll_contexts(rolls, check_rolls, &mut errors, &mut sync)?
+                }
+                Err(err) => {
+                    errors.push(format!("Couldn't work out the rolls: {err}"));
+                    sync = GameTickSync::Unparsed;
+                    Vec::new()
+                }
+            };
 
             let prev = game_at_tick.runners_at_end;
-            game.set_prev(prev);
-            Ok(GameTickContext {
-                game_label,
-                description: update.data.last_update,
-                errors,
-                warnings,
-                rolls,
-            })
+            game.set_prev(prev, update);
+            Ok((
+                GameTickContext {
+                    game_label,
+                    description: update.data.last_update.clone(),
+                    inferred: false,
+                    validation_errors,
+                    validation_warnings,
+                    errors,
+                    warnings: Vec::new(),
+                    rolls,
+                },
+                sync,
+            ))
         }
         Err(err) => {
             errors.push(format!("Parse error: {err}"));
             let prev = game_at_tick.runners_at_end;
-            game.set_prev(prev);
-            Ok(GameTickContext {
+            game.set_prev(prev, update);
+            Ok((
+                GameTickContext {
+                    game_label,
+                    description: update.data.last_update.clone(),
+                    inferred: false,
+                    validation_errors,
+                    validation_warnings,
+                    errors,
+                    warnings: Vec::new(),
+                    rolls: Vec::new(),
+                },
+                GameTickSync::Unparsed,
+            ))
+        }
+    }
+}
+
+// Checks a game tick's rolls against what the update says and against resim,
+// and turns them into what the template shows
+fn roll_contexts(
+    mut rolls: Vec<RollData>,
+    check_rolls: &mut Option<RollStream>,
+    errors: &mut Vec<String>,
+    sync: &mut GameTickSync,
+) -> Result<Vec<RollContext>, EngineFatalError> {
+    let contradictions = infer_outcomes(&mut rolls);
+    if !contradictions.is_empty() {
+        *sync = GameTickSync::Mismatch;
+        errors.extend(contradictions);
+    }
+
+    // Everything after the first bad threshold roll is suspect, so
+    // only that one gets an error
+    let mut reported_bad_threshold = false;
+    rolls
+        .into_iter()
+        .map(|roll_data| {
+            if roll_data.agrees_with_outcome() == Some(false) {
+                *sync = GameTickSync::Mismatch;
+                match &roll_data.usage {
+                    RollUsage::Choice {
+                        selected_option_description: Some(selected),
+                        observed_option_description: Some(observed),
+                        ..
+                    } => {
+                        errors.push(format!(
+                            "{} chose {selected}, but the update says {observed}",
+                            roll_data.purpose
+                        ));
+                    }
+                    RollUsage::Threshold {
+                        threshold: Some(threshold),
+                        passed: Some(passed),
+                        ..
+                    } if !reported_bad_threshold => {
+                        reported_bad_threshold = true;
+                        errors.push(format!(
+                            "\"{}\" rolled {} against {threshold}, but it must have {}",
+                            roll_data.purpose,
+                            roll_data.roll,
+                            if *passed { "passed" } else { "failed" },
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(check_rolls) = check_rolls {
+                // TODO This is the least efficient way to do it
+                if let Some(check_roll) = check_rolls.pop_front() {
+                    if check_roll.roll != roll_data.roll {
+                        *sync = GameTickSync::Mismatch;
+                    }
+                    Ok(RollContext::for_roll(roll_data, Some(check_roll)))
+                } else {
+                    Err(EngineFatalError::RanOutOfCheckRolls)
+                }
+            } else {
+                Ok(RollContext::for_roll(roll_data, None))
+            }
+        })
+        .collect()
+}
+
+// One run of updates that could fill a gap, worked out against a copy of the
+// Rng
+pub struct ReconstructedGap {
+    pub missing: Vec<ParsedUpdateData<'static>>,
+    pub rolls: Vec<Vec<RollData>>,
+    // Where the Rng is after the missing updates' rolls
+    pub rng: Rng,
+}
+
+// What Chron missed before an update. The tick order search tries every
+// reconstruction, since which one is right can depend on the rolls after it.
+pub enum MissingUpdates {
+    // Nothing was missed, or the update didn't parse and gets its own error
+    None,
+    // Updates were missed, but we couldn't work out which
+    Unreconstructed { gap: i64, reason: String },
+    // Never empty. The ones whose rolls agree best with the updates they're
+    // for come first.
+    Reconstructed(Vec<ReconstructedGap>),
+}
+
+// Chron sometimes misses updates. When the play count skips ahead, find every
+// short run of simple updates that gets from the state we had to the state
+// this update leaves behind. Their rolls have to happen before this update's,
+// or every roll after them is off. The start of a game is always the same, so
+// if Chron missed some of it we know exactly what.
+pub fn reconstruct_missing_updates(
+    game: &sim::Game,
+    update: &ChroniclerGameUpdate,
+    th: &Thresholds,
+    rng: &Rng,
+) -> MissingUpdates {
+    let Ok(parsed_update) = update_parser::parse_update(update) else {
+        return MissingUpdates::None;
+    };
+
+    match game.tracked_state() {
+        None => {
+            let Some(missing) = missed_game_start(&parsed_update.data, &update.data) else {
+                return MissingUpdates::None;
+            };
+            let game_at_tick = game.at_tick(update);
+            let mut rng = rng.clone();
+            // Only hits can fail to produce rolls, and the game start has none
+            let Ok(rolls) = missing
+                .iter()
+                .map(|missing_update| {
+                    let parsed = ParsedUpdate {
+                        data: missing_update.clone(),
+                    };
+                    rolls_for_update(&mut rng, parsed, th, &game_at_tick)
+                })
+                .collect::<Result<Vec<_>, _>>()
+            else {
+                return MissingUpdates::None;
+            };
+            MissingUpdates::Reconstructed(vec![ReconstructedGap {
+                missing: missing.to_vec(),
+                rolls,
+                rng,
+            }])
+        }
+        Some(tracked_state) => {
+            let gap = game.missing_updates(update);
+            if gap == 0 {
+                return MissingUpdates::None;
+            }
+            if gap > MAX_RECONSTRUCTED_GAP {
+                return MissingUpdates::Unreconstructed {
+                    gap,
+                    reason: format!(
+                        "Chron is missing {gap} updates here, which is too many to work out"
+                    ),
+                };
+            }
+
+            let rules = GameRules::from_update(&update.data);
+            let candidates = tracked_state.gap_candidates(
+                gap as usize,
+                &parsed_update.data,
+                &rules,
+                &TrackedState::from_update(&update.data),
+            );
+
+            // Each candidate, and how many of its rolls disagreed with the
+            // update they're for
+            let mut reconstructed = candidates
+                .into_iter()
+                .filter_map(|missing| {
+                    let mut candidate_rng = rng.clone();
+                    let mut state = tracked_state.clone();
+                    let mut contradictions = 0;
+                    let rolls = missing
+                        .iter()
+                        .map(|missing_update| {
+                            let game_at_tick = game.at_missing_tick(update, &state);
+                            let parsed = ParsedUpdate {
+                                data: missing_update.clone(),
+                            };
+                            let mut rolls =
+                                rolls_for_update(&mut candidate_rng, parsed, th, &game_at_tick)?;
+                            contradictions += infer_outcomes(&mut rolls).len();
+                            contradictions += rolls
+                                .iter()
+                                .filter(|roll| roll.agrees_with_outcome() == Some(false))
+                                .count();
+                            state.apply(missing_update, &rules);
+                            Ok(rolls)
+                        })
+                        .collect::<Result<Vec<_>, RollsError>>();
+                    // A candidate we can't produce rolls for can't be right
+                    let gap = ReconstructedGap {
+                        missing,
+                        rolls: rolls.ok()?,
+                        rng: candidate_rng,
+                    };
+                    Some((gap, contradictions))
+                })
+                .collect_vec();
+            if reconstructed.is_empty() {
+                return MissingUpdates::Unreconstructed {
+                    gap,
+                    reason: format!(
+                        "Chron is missing {gap} update(s) here, and no simple updates fit"
+                    ),
+                };
+            }
+
+            // Stable, so ties stay in the order `gap_candidates` gave them
+            reconstructed.sort_by_key(|(_, contradictions)| *contradictions);
+            MissingUpdates::Reconstructed(reconstructed.into_iter().map(|(gap, _)| gap).collect())
+        }
+    }
+}
+
+// Runs the reconstruction the tick order search chose for real, moving the Rng
+// and the game past it. `gap_choice` indexes into what
+// `reconstruct_missing_updates` found.
+fn run_missing_updates(
+    game: &mut sim::Game,
+    update: &ChroniclerGameUpdate,
+    th: &Thresholds,
+    rng: &mut Rng,
+    check_rolls: &mut Option<RollStream>,
+    gap_choice: usize,
+) -> Result<Vec<GameTickContext>, EngineFatalError> {
+    let game_label = format!(
+        "{} @ {}",
+        update.data.away_team_nickname, update.data.home_team_nickname
+    );
+
+    let (missing, rolls) = match reconstruct_missing_updates(game, update, th, rng) {
+        MissingUpdates::None => return Ok(Vec::new()),
+        MissingUpdates::Unreconstructed { gap, reason } => {
+            return Ok(vec![GameTickContext {
                 game_label,
-                description: update.data.last_update,
-                errors,
-                warnings,
+                description: format!("{gap} missing update(s)"),
+                inferred: true,
+                validation_errors: Vec::new(),
+                validation_warnings: Vec::new(),
+                errors: Vec::new(),
+                warnings: vec![reason],
                 rolls: Vec::new(),
+            }]);
+        }
+        MissingUpdates::Reconstructed(mut reconstructed) => {
+            // A resync earlier in the tick moves the Rng, and then the search
+            // may have seen different reconstructions than we do now
+            let gap_choice = if gap_choice < reconstructed.len() {
+                gap_choice
+            } else {
+                0
+            };
+            let ReconstructedGap {
+                missing,
+                rolls,
+                rng: rng_after,
+            } = reconstructed.swap_remove(gap_choice);
+            *rng = rng_after;
+            // A missed game start has no state to move forward yet
+            if game.tracked_state().is_some() {
+                game.fill_gap(&missing, update);
+            }
+            (missing, rolls)
+        }
+    };
+
+    iter::zip(&missing, rolls)
+        .map(|(missing_update, rolls)| {
+            let mut errors = Vec::new();
+            // Mismatches here still show up as errors, but only the real
+            // updates start a resync
+            let mut sync = GameTickSync::InSync;
+            let rolls = roll_contexts(rolls, check_rolls, &mut errors, &mut sync)?;
+            Ok(GameTickContext {
+                game_label: game_label.clone(),
+                description: describe_gap_update(missing_update).to_string(),
+                inferred: true,
+                validation_errors: Vec::new(),
+                validation_warnings: Vec::new(),
+                errors,
+                warnings: Vec::new(),
+                rolls,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blaseball_api::ChroniclerGameUpdateData;
+    use serde_json::json;
+
+    // A regular-season update in the top of the first, with a perfectly
+    // ordinary count and no one on base. Tests change what they need with
+    // `..data(play_count, last_update)`.
+    fn data(play_count: i64, last_update: &str) -> ChroniclerGameUpdateData {
+        ChroniclerGameUpdateData {
+            id: Uuid::from_u128(1),
+            day: 10,
+            phase: 2,
+            rules: None,
+            shame: false,
+            inning: 0,
+            season: 11,
+            weather: 1,
+            away_odds: 0.5,
+            away_outs: 3,
+            away_team: Uuid::from_u128(100),
+            home_odds: 0.5,
+            home_outs: 3,
+            home_team: Uuid::from_u128(200),
+            outcomes: Vec::new(),
+            away_balls: 4,
+            away_bases: 4,
+            away_score: 0.,
+            finalized: false,
+            game_start: true,
+            home_balls: 4,
+            home_bases: 4,
+            home_score: 0.,
+            play_count,
+            stadium_id: None,
+            statsheet: None,
+            at_bat_balls: 0,
+            away_batter: None,
+            home_batter: None,
+            last_update: last_update.to_string(),
+            tournament: -1,
+            away_pitcher: None,
+            away_strikes: 3,
+            base_runners: Vec::new(),
+            home_pitcher: None,
+            home_strikes: 3,
+            repeat_count: 0,
+            score_ledger: String::new(),
+            score_update: String::new(),
+            series_index: 1,
+            terminology: Uuid::from_u128(2),
+            top_of_inning: true,
+            at_bat_strikes: 0,
+            away_team_name: "Away Team".to_string(),
+            game_complete: false,
+            home_team_name: "Home Team".to_string(),
+            is_postseason: false,
+            is_title_match: false,
+            series_length: 3,
+            away_batter_mod: String::new(),
+            away_team_color: String::new(),
+            away_team_emoji: String::new(),
+            bases_occupied: Vec::new(),
+            home_batter_mod: String::new(),
+            home_team_color: String::new(),
+            home_team_emoji: String::new(),
+            away_batter_name: String::new(),
+            away_pitcher_mod: String::new(),
+            base_runner_mods: Vec::new(),
+            game_start_phase: 10,
+            half_inning_outs: 0,
+            home_batter_name: String::new(),
+            home_pitcher_mod: String::new(),
+            new_inning_phase: -1,
+            top_inning_score: 0.,
+            away_pitcher_name: "Away Pitcher".to_string(),
+            base_runner_names: Vec::new(),
+            baserunner_count: 0,
+            half_inning_score: 0.,
+            home_pitcher_name: "Home Pitcher".to_string(),
+            away_team_nickname: "Away".to_string(),
+            home_team_nickname: "Home".to_string(),
+            secret_baserunner: None,
+            bottom_inning_score: 0,
+            away_team_batter_count: 0,
+            home_team_batter_count: -1,
+            away_team_secondary_color: String::new(),
+            home_team_secondary_color: String::new(),
+        }
+    }
+
+    fn update(data: ChroniclerGameUpdateData) -> ChroniclerGameUpdate {
+        ChroniclerGameUpdate {
+            game_id: Uuid::from_u128(3),
+            timestamp: "2021-03-01T16:00:00Z".parse().unwrap(),
+            data,
+        }
+    }
+
+    // A game that has already processed `prev`, with its runners on base
+    fn game_after(prev: &ChroniclerGameUpdate) -> sim::Game {
+        let mut game = sim::Game::average();
+        game.track_state(None, prev);
+        let runners = iter::zip(&prev.data.bases_occupied, &prev.data.base_runners)
+            .map(|(&base, &runner_id)| sim::RunnerOnBase { base, runner_id })
+            .collect();
+        game.set_prev(runners, prev);
+        game
+    }
+
+    fn rng() -> Rng {
+        Rng::new((11489856334623440466, 7665746933450455135), 0)
+    }
+
+    fn descriptions(contexts: &[GameTickContext]) -> Vec<&str> {
+        contexts
+            .iter()
+            .map(|context| context.description.as_str())
+            .collect()
+    }
+
+    // The template picks the message by `kind`
+    #[test]
+    fn validation_issues_are_tagged_with_their_kind() {
+        let warning = ValidationWarning::PlayCountGap {
+            last: 3,
+            current: 5,
+        };
+        assert_eq!(
+            serde_json::to_value(&warning).unwrap(),
+            json!({ "kind": "PlayCountGap", "last": 3, "current": 5 })
+        );
+    }
+
+    #[test]
+    fn fills_in_a_missed_pitch() {
+        let th = Thresholds::load().unwrap();
+        let prev = update(data(5, "Away Batter 1 batting for the Away."));
+        let mut game = game_after(&prev);
+        let next = update(ChroniclerGameUpdateData {
+            at_bat_balls: 1,
+            at_bat_strikes: 1,
+            ..data(7, "Strike, looking. 1-1")
+        });
+
+        let mut rng = rng();
+        let contexts = run_missing_updates(&mut game, &next, &th, &mut rng, &mut None, 0).unwrap();
+
+        assert_eq!(descriptions(&contexts), ["Ball"]);
+        assert!(contexts[0].inferred);
+        let mut expected_rng = self::rng();
+        for _ in &contexts[0].rolls {
+            expected_rng.next();
+        }
+        assert_eq!(rng, expected_rng);
+        assert_eq!(game.tracked_state().unwrap().balls, 1);
+        assert_eq!(game.missing_updates(&next), 0);
+    }
+
+    #[test]
+    fn a_gap_across_a_turnover_uses_the_new_half_inning() {
+        let th = Thresholds::load().unwrap();
+        let prev = update(ChroniclerGameUpdateData {
+            away_team_batter_count: 3,
+            ..data(20, "Inning 1 is now an Outing.")
+        });
+        let mut game = game_after(&prev);
+        let next = update(ChroniclerGameUpdateData {
+            top_of_inning: false,
+            away_team_batter_count: 3,
+            home_team_batter_count: 0,
+            ..data(22, "Home Batter 1 batting for the Home.")
+        });
+
+        let mut rng = rng();
+        let contexts = run_missing_updates(&mut game, &next, &th, &mut rng, &mut None, 0).unwrap();
+
+        assert_eq!(descriptions(&contexts), ["Inning turnover"]);
+        assert!(!game.tracked_state().unwrap().top_of_inning);
+    }
+
+    #[test]
+    fn reconstructs_a_missed_game_start() {
+        let th = Thresholds::load().unwrap();
+        let mut game = sim::Game::average();
+        let first = update(ChroniclerGameUpdateData {
+            is_postseason: true,
+            ..data(1, "Play ball!")
+        });
+
+        let mut rng = rng();
+        let contexts = run_missing_updates(&mut game, &first, &th, &mut rng, &mut None, 0).unwrap();
+
+        assert_eq!(descriptions(&contexts), ["Let's Go!"]);
+        // Postseason weather, then odds fuzzing
+        assert_eq!(contexts[0].rolls.len(), 2);
+        let mut expected_rng = self::rng();
+        expected_rng.next();
+        expected_rng.next();
+        assert_eq!(rng, expected_rng);
+    }
+
+    // The rolls for a gap come before the update's own, so the tick order
+    // search has to count them or it lines the update up with the wrong rolls
+    #[test]
+    fn tick_order_runs_the_gap_rolls_first() {
+        let th = Thresholds::load().unwrap();
+        let other_game = Uuid::from_u128(4);
+        let prev = || update(data(5, "Away Batter 1 batting for the Away."));
+        // Chron missed a ball before this one
+        let with_gap = || {
+            update(ChroniclerGameUpdateData {
+                at_bat_balls: 1,
+                at_bat_strikes: 1,
+                ..data(7, "Strike, looking. 1-1")
+            })
+        };
+        let without_gap = || ChroniclerGameUpdate {
+            game_id: other_game,
+            ..update(ChroniclerGameUpdateData {
+                at_bat_balls: 1,
+                ..data(6, "Ball. 1-0")
             })
+        };
+
+        // Somewhere every roll agrees with what the updates say, so the only
+        // contradictions come from the order
+        let start = || {
+            let mut rng = rng();
+            rng.step(74);
+            rng
+        };
+
+        // Resim saw the game without a gap go first
+        let mut rng = start();
+        let mut check_rolls = Vec::new();
+        for next in [without_gap(), with_gap()] {
+            let mut game = game_after(&prev());
+            let mut contexts =
+                run_missing_updates(&mut game, &next, &th, &mut rng, &mut None, 0).unwrap();
+            contexts.push(
+                run_game_tick(&mut game, &next, &th, &mut rng, &mut None)
+                    .unwrap()
+                    .0,
+            );
+            for roll in contexts.iter().flat_map(|context| &context.rolls) {
+                check_rolls.push(
+                    serde_json::from_value(json!({
+                        "label": roll.purpose,
+                        "roll": roll.roll,
+                        "passed": null,
+                        "threshold": null,
+                    }))
+                    .unwrap(),
+                );
+            }
         }
+        let check_rolls = RollStream::new(check_rolls);
+
+        let games = HashMap::from([
+            (with_gap().game_id, game_after(&prev())),
+            (other_game, game_after(&prev())),
+        ]);
+        let tick_order = solve_tick_order(
+            &games,
+            &[with_gap(), without_gap()],
+            &th,
+            &start(),
+            Some(&check_rolls),
+        );
+
+        assert_eq!(tick_order.order, [1, 0]);
+        assert_eq!(tick_order.contradictions, 0);
+        assert!(!tick_order.ambiguous);
+    }
+
+    #[test]
+    fn a_party_rolls_once_per_boost() {
+        let th = Thresholds::load().unwrap();
+        let prev = update(data(5, "Away Batter 1 batting for the Away."));
+        let mut game = game_after(&prev);
+        let next = update(data(
+            6,
+            "Away Batter 1 is Partying!\nTheir Moxie and Base Thirst are boosted.",
+        ));
+
+        let (context, _) = run_game_tick(&mut game, &next, &th, &mut rng(), &mut None).unwrap();
+
+        let boosts = context
+            .rolls
+            .iter()
+            .map(|roll| roll.purpose.as_str())
+            .filter(|purpose| purpose.starts_with("How much did the party boost"))
+            .collect_vec();
+        assert_eq!(
+            boosts,
+            [
+                "How much did the party boost Moxie?",
+                "How much did the party boost BaseThirst?"
+            ]
+        );
+    }
+
+    // The runner taken off is whoever the roll picks, and the update only
+    // tells us whether that was right
+    #[test]
+    fn checks_the_double_play_runner_against_the_roll() {
+        let th = Thresholds::load().unwrap();
+        let on_first_and_second = |play_count, last_update| ChroniclerGameUpdateData {
+            base_runners: vec![Uuid::from_u128(102), Uuid::from_u128(103)],
+            base_runner_names: vec!["Away Batter 2".to_string(), "Away Batter 3".to_string()],
+            base_runner_mods: vec![String::new(), String::new()],
+            bases_occupied: vec![1, 0],
+            baserunner_count: 2,
+            away_batter: Some(Uuid::from_u128(104)),
+            away_batter_name: "Away Batter 4".to_string(),
+            away_team_batter_count: 3,
+            ..data(play_count, last_update)
+        };
+        let prev = update(on_first_and_second(
+            5,
+            "Away Batter 4 batting for the Away.",
+        ));
+        let mut game = game_after(&prev);
+        let next = update(ChroniclerGameUpdateData {
+            base_runners: vec![Uuid::from_u128(102)],
+            base_runner_names: vec!["Away Batter 2".to_string()],
+            base_runner_mods: vec![String::new()],
+            bases_occupied: vec![1],
+            baserunner_count: 1,
+            half_inning_outs: 2,
+            away_batter: None,
+            away_batter_name: String::new(),
+            ..on_first_and_second(6, "Away Batter 4 hit into a double play!")
+        });
+
+        let (context, _) = run_game_tick(&mut game, &next, &th, &mut rng(), &mut None).unwrap();
+
+        let roll = context
+            .rolls
+            .iter()
+            .find(|roll| roll.purpose == RollPurpose::DoublePlayWhere.to_string())
+            .unwrap();
+        let RollUsage::Choice {
+            num_options,
+            selected_option: Some(selected),
+            selected_option_description,
+            observed_option_description,
+        } = &roll.usage
+        else {
+            panic!("the double play roll should be a choice");
+        };
+        assert_eq!(*num_options, 2);
+        assert_eq!(*selected, (roll.roll * 2.) as usize);
+        let runners = ["Away Batter 2", "Away Batter 3"];
+        assert_eq!(
+            selected_option_description.as_deref(),
+            Some(runners[*selected])
+        );
+        assert_eq!(
+            observed_option_description.as_deref(),
+            Some("Away Batter 3")
+        );
+    }
+
+    #[test]
+    fn gives_up_on_a_long_gap() {
+        let th = Thresholds::load().unwrap();
+        let prev = update(data(5, "Away Batter 1 batting for the Away."));
+        let mut game = game_after(&prev);
+        let next = update(ChroniclerGameUpdateData {
+            at_bat_balls: 1,
+            at_bat_strikes: 1,
+            ..data(5 + MAX_RECONSTRUCTED_GAP + 2, "Strike, looking. 1-1")
+        });
+
+        let mut rng = rng();
+        let contexts = run_missing_updates(&mut game, &next, &th, &mut rng, &mut None, 0).unwrap();
+
+        assert_eq!(contexts.len(), 1);
+        assert!(contexts[0].rolls.is_empty());
+        assert_eq!(contexts[0].warnings.len(), 1);
+        assert_eq!(rng, self::rng());
     }
 }
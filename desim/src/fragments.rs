@@ -1,6 +1,9 @@
 use crate::nom_helpers::{parse_terminated, parse_whole_number};
+use crate::resync::ResyncConfig;
+use crate::rng::solver::{solve_rng, RngSolveError, RollConstraint};
 use crate::rng::Rng;
 use crate::rolls::RollPurpose;
+use crate::sim::Weather;
 use chrono::{DateTime, Utc};
 use flate2::bufread::GzDecoder;
 use itertools::Itertools;
@@ -8,7 +11,7 @@ use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::{Finish, Parser};
 use serde::{Deserialize, Deserializer};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read};
 use tar::Archive;
 use thiserror::Error;
@@ -23,6 +26,7 @@ pub struct Fragment {
     pub end_time: DateTime<Utc>,
     pub rng: Rng,
     pub check_rolls: Option<RollStream>,
+    pub resync: Option<ResyncConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -76,10 +80,32 @@ fn parse_advance(input: &str) -> ParserResult<(i64, bool)> {
     Ok((input, (num, b)))
 }
 
+fn parse_advance_on_out(input: &str) -> ParserResult<(i64, String)> {
+    let (input, _) = tag("adv? ").parse(input)?;
+    let (input, num) = parse_whole_number.parse(input)?;
+    let (input, _) = tag("/").parse(input)?;
+    // Followed by whether resim thought they advanced, which we work out
+    // for ourselves
+    let (input, name) = parse_terminated(" (").parse(input)?;
+    Ok((input, (num, name.to_string())))
+}
+
 fn parse_anything(input: &str) -> ParserResult<&str> {
     Ok(("", input))
 }
 
+fn parse_weather_purpose(input: &str) -> ParserResult<Weather> {
+    alt((
+        tag("eclipse").map(|_| Weather::SolarEclipse),
+        tag("blooddrain").map(|_| Weather::Blooddrain),
+        tag("peanuts").map(|_| Weather::Peanuts),
+        tag("birds").map(|_| Weather::Birds),
+        tag("feedback").map(|_| Weather::Feedback),
+        tag("reverb").map(|_| Weather::Reverb),
+    ))
+    .parse(input)
+}
+
 fn parse_roll_purpose(input: &str) -> ParserResult<RollPurpose> {
     alt((
         tag("party time").map(|_| RollPurpose::Party),
@@ -95,12 +121,24 @@ fn parse_roll_purpose(input: &str) -> ParserResult<RollPurpose> {
         tag("home run").map(|_| RollPurpose::HomeRun),
         parse_to("double").map(|name| RollPurpose::Double(name.to_string())),
         parse_to("triple").map(|name| RollPurpose::Triple(name.to_string())),
-        parse_steal.map(|val| RollPurpose::Steal(val)),
-        parse_advance.map(|val| RollPurpose::Advance(val)),
+        // The steal success roll doesn't say which base it's for
+        alt((
+            tag("steal success").map(|_| RollPurpose::StealSuccess),
+            parse_steal.map(|val| RollPurpose::Steal(val)),
+        )),
+        alt((
+            parse_advance_on_out.map(RollPurpose::AdvanceOnOut),
+            parse_advance.map(|val| RollPurpose::Advance(val)),
+        )),
         tag("dp?").map(|_| RollPurpose::DoublePlayHappens),
         tag("dp where").map(|_| RollPurpose::DoublePlayWhere),
         tag("target team (not partying)").map(|_| RollPurpose::PartyTargetTeam),
         parse_advance.map(|_| RollPurpose::PartyTargetTeam),
+        alt((
+            parse_weather_purpose.map(RollPurpose::Weather),
+            tag("postseason weather").map(|_| RollPurpose::PostseasonWeather),
+            tag("odds fuzzing").map(|_| RollPurpose::OddsFuzzing),
+        )),
         parse_anything.map(|v| RollPurpose::Unparsed(v.to_string())),
     ))
     .parse(input)
@@ -135,6 +173,15 @@ pub enum LoadFragmentsError {
 
     #[error("Invalid JSON in roll streams archive: {0}")]
     InvalidJsonInRollStreamsArchive(serde_json::Error),
+
+    #[error("Fragment \"{0}\" must have exactly one of `rng` and `solve_rng`")]
+    AmbiguousRng(String),
+
+    #[error("Fragment \"{0}\" uses `solve_rng`, but it has no roll stream to solve from")]
+    NoRollStreamToSolveFrom(String),
+
+    #[error("Couldn't solve for the Rng of fragment \"{0}\": {1}")]
+    SolveRng(String, RngSolveError),
 }
 
 // This is what we deserialize from disk
@@ -143,9 +190,23 @@ struct FragmentSpec {
     pub label: String,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
-    pub rng: Rng,
-    pub initial_step: Option<i32>,
+    // Exactly one of these says where the Rng starts
+    pub rng: Option<Rng>,
+    pub solve_rng: Option<SolveRngSpec>,
+    pub initial_step: Option<i64>,
     pub roll_stream: Option<RollStreamSpec>,
+    // Enables searching for a new Rng position when rolls desync
+    pub resync: Option<ResyncConfig>,
+}
+
+// Recovers the Rng from the fragment's roll stream instead of taking it from
+// `rng`. It's the Rng from just before the stream's first roll.
+#[derive(Debug, Clone, Deserialize)]
+struct SolveRngSpec {
+    // How many of the stream's rolls to use, after `skip_lines`. A couple of
+    // rolls pin down the state, but telling the block offsets apart takes a
+    // full block of 64.
+    rolls: usize,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -154,7 +215,47 @@ struct RollStreamSpec {
     pub skip_lines: Option<usize>,
 }
 
-pub type RollStream = VecDeque<CheckRoll>;
+// Resim's rolls in order, and how far into them we've gotten. Resyncing can
+// move the Rng backwards, so the rolls we've used have to stick around.
+#[derive(Debug, Clone)]
+pub struct RollStream {
+    rolls: Vec<CheckRoll>,
+    position: usize,
+}
+
+impl RollStream {
+    pub fn new(rolls: Vec<CheckRoll>) -> Self {
+        Self { rolls, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // The roll `ahead` rolls after the next unused one
+    pub fn get(&self, ahead: usize) -> Option<&CheckRoll> {
+        self.rolls.get(self.position + ahead)
+    }
+
+    pub fn pop_front(&mut self) -> Option<CheckRoll> {
+        let roll = self.rolls.get(self.position)?.clone();
+        self.position += 1;
+        Some(roll)
+    }
+
+    // Moves forwards or backwards by `rolls`, the same way `Rng::step` does.
+    // Returns false without moving if that would leave the stream.
+    pub fn step(&mut self, rolls: i64) -> bool {
+        let Some(position) = self.position.checked_add_signed(rolls as isize) else {
+            return false;
+        };
+        if position > self.rolls.len() {
+            return false;
+        }
+        self.position = position;
+        true
+    }
+}
 
 pub fn load_fragments() -> Result<Fragments, LoadFragmentsError> {
     // TODO Rocket is sensitive to cwd anyway, so there's no advantage to
@@ -234,14 +335,30 @@ fn load_roll_stream(entry: impl Read, skip_lines: usize) -> Result<RollStream, L
             serde_json::from_str(&line)
                 .map_err(|e| LoadFragmentsError::InvalidJsonInRollStreamsArchive(e.into()))
         })
-        .collect()
+        .collect::<Result<_, _>>()
+        .map(RollStream::new)
 }
 
 fn fragment_from_spec(
     spec: FragmentSpec,
     roll_streams: &mut HashMap<String, RollStream>,
 ) -> Result<Fragment, LoadFragmentsError> {
-    let mut rng = spec.rng;
+    let check_rolls = spec
+        .roll_stream
+        .map(|s| get_roll_stream(s, roll_streams))
+        .transpose()?;
+
+    let mut rng = match (spec.rng, spec.solve_rng) {
+        (Some(rng), None) => rng,
+        (None, Some(solve)) => {
+            let Some(check_rolls) = &check_rolls else {
+                return Err(LoadFragmentsError::NoRollStreamToSolveFrom(spec.label));
+            };
+            solve_rng_from_stream(check_rolls, solve.rolls)
+                .map_err(|err| LoadFragmentsError::SolveRng(spec.label.clone(), err))?
+        }
+        _ => return Err(LoadFragmentsError::AmbiguousRng(spec.label)),
+    };
     if let Some(step_by) = spec.initial_step {
         rng.step(step_by);
     }
@@ -250,13 +367,19 @@ fn fragment_from_spec(
         start_time: spec.start_time,
         end_time: spec.end_time,
         rng,
-        check_rolls: spec
-            .roll_stream
-            .map(|s| get_roll_stream(s, roll_streams))
-            .transpose()?,
+        check_rolls,
+        resync: spec.resync,
     })
 }
 
+fn solve_rng_from_stream(check_rolls: &RollStream, rolls: usize) -> Result<Rng, RngSolveError> {
+    let constraints = (0..rolls)
+        .map_while(|index| Some((index, check_rolls.get(index)?)))
+        .map(|(index, roll)| RollConstraint::from_check_roll(index, roll))
+        .collect();
+    solve_rng(constraints)
+}
+
 fn get_roll_stream(
     spec: RollStreamSpec,
     roll_streams: &mut HashMap<String, RollStream>,
@@ -265,3 +388,23 @@ fn get_roll_stream(
         .remove(spec.file.as_str())
         .ok_or_else(|| LoadFragmentsError::MissingRollStream(spec.file))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The bundled fragment's Rng was copied from resim by hand, so solving
+    // from the same roll stream has to land on it
+    #[test]
+    fn solves_the_bundled_fragment_rng() {
+        let file = "s11-2021-03-06T19:26:00Z.ndjson".to_string();
+        let mut streams = load_roll_streams(HashMap::from([(file.clone(), 105)])).unwrap();
+
+        let rng = solve_rng_from_stream(&streams.remove(&file).unwrap(), 100).unwrap();
+
+        assert_eq!(
+            rng,
+            Rng::new((15344562644745423164, 10882960106955666841), 23)
+        );
+    }
+}
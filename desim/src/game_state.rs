@@ -0,0 +1,620 @@
+use blaseball_api::ChroniclerGameUpdateData;
+use itertools::Itertools;
+use std::iter;
+
+use crate::update_parser::ParsedUpdateData;
+
+// The parts of a game's state that desim keeps track of itself, only ever
+// moving it forward from what the update parser says happened. Comparing it
+// to Chron after every update is what catches a misparse, since the rolls for
+// a misparsed update can still line up by accident.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedState {
+    pub top_of_inning: bool,
+    pub balls: i64,
+    pub strikes: i64,
+    pub outs: i64,
+    pub away_score: f64,
+    pub home_score: f64,
+    pub away_batter_count: i64,
+    pub home_batter_count: i64,
+    // None when the update doesn't say enough to know where every runner
+    // ended up, like a hit with runners on. Advances on hits aren't in the
+    // text at all.
+    pub runners: Option<Vec<TrackedRunner>>,
+}
+
+// The updates we can reconstruct when Chron misses some. They're the ones
+// that don't name anyone, so the state before and after is all there is to go
+// on anyway.
+const GAP_CANDIDATES: [ParsedUpdateData<'static>; 7] = [
+    ParsedUpdateData::BatterUp,
+    ParsedUpdateData::Ball,
+    ParsedUpdateData::FoulBall,
+    ParsedUpdateData::StrikeLooking,
+    ParsedUpdateData::StrikeSwinging,
+    ParsedUpdateData::InningEnd,
+    ParsedUpdateData::InningTurnover,
+];
+
+// Every candidate is tried at every position, so this grows fast
+pub const MAX_RECONSTRUCTED_GAP: i64 = 3;
+
+// How every game starts, before the first half-inning's turnover
+const GAME_START: [ParsedUpdateData<'static>; 2] =
+    [ParsedUpdateData::LetsGo, ParsedUpdateData::PlayBall];
+
+// When the first update we get for a game is still part of the start, these
+// are the updates Chron missed before it. They're always the same ones, so
+// there's nothing to search for. None if we joined the game after the start.
+pub fn missed_game_start(
+    first: &ParsedUpdateData,
+    data: &ChroniclerGameUpdateData,
+) -> Option<&'static [ParsedUpdateData<'static>]> {
+    match first {
+        ParsedUpdateData::LetsGo => Some(&GAME_START[..0]),
+        ParsedUpdateData::PlayBall => Some(&GAME_START[..1]),
+        ParsedUpdateData::InningTurnover if data.inning == 0 && data.top_of_inning => {
+            Some(&GAME_START[..])
+        }
+        _ => None,
+    }
+}
+
+pub fn describe_gap_update(update: &ParsedUpdateData) -> &'static str {
+    match update {
+        ParsedUpdateData::LetsGo => "Let's Go!",
+        ParsedUpdateData::PlayBall => "Play ball!",
+        ParsedUpdateData::BatterUp => "Batter up",
+        ParsedUpdateData::Ball => "Ball",
+        ParsedUpdateData::FoulBall => "Foul ball",
+        ParsedUpdateData::StrikeLooking => "Strike, looking",
+        ParsedUpdateData::StrikeSwinging => "Strike, swinging",
+        ParsedUpdateData::InningEnd => "Inning end",
+        ParsedUpdateData::InningTurnover => "Inning turnover",
+        _ => "Something else",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedRunner {
+    // Same numbering as `bases_occupied`, so first base is 0
+    pub base: i64,
+    pub name: String,
+}
+
+// Rules for one half-inning, which come from Chron rather than being tracked
+#[derive(Debug, Copy, Clone)]
+pub struct HalfInningRules {
+    pub away_batting: bool,
+    pub max_strikes: i64,
+    pub max_outs: i64,
+    // Including home, so this is 4 for a normal game
+    pub num_bases: i64,
+}
+
+// Each team has its own rules, so a gap that crosses a turnover has to switch
+// between them partway through
+#[derive(Debug, Copy, Clone)]
+pub struct GameRules {
+    pub top: HalfInningRules,
+    pub bottom: HalfInningRules,
+}
+
+impl GameRules {
+    pub fn from_update(data: &ChroniclerGameUpdateData) -> Self {
+        Self {
+            top: HalfInningRules::for_half(data, true),
+            bottom: HalfInningRules::for_half(data, false),
+        }
+    }
+}
+
+impl HalfInningRules {
+    fn for_half(data: &ChroniclerGameUpdateData, top_of_inning: bool) -> Self {
+        if top_of_inning {
+            Self {
+                away_batting: true,
+                max_strikes: data.away_strikes,
+                max_outs: data.away_outs,
+                num_bases: data.away_bases,
+            }
+        } else {
+            Self {
+                away_batting: false,
+                max_strikes: data.home_strikes,
+                max_outs: data.home_outs,
+                num_bases: data.home_bases,
+            }
+        }
+    }
+}
+
+impl TrackedState {
+    pub fn from_update(data: &ChroniclerGameUpdateData) -> Self {
+        Self {
+            top_of_inning: data.top_of_inning,
+            balls: data.at_bat_balls,
+            strikes: data.at_bat_strikes,
+            outs: data.half_inning_outs,
+            away_score: data.away_score,
+            home_score: data.home_score,
+            away_batter_count: data.away_team_batter_count,
+            home_batter_count: data.home_team_batter_count,
+            runners: Some(
+                data.bases_occupied
+                    .iter()
+                    .zip(&data.base_runner_names)
+                    .map(|(&base, name)| TrackedRunner {
+                        base,
+                        name: name.clone(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // Moves the state forward by one update, compares it with what Chron says
+    // the state is after that update, and returns every difference. Either
+    // way it ends up matching Chron, so one misparse is one set of errors
+    // instead of a cascade.
+    pub fn advance_and_compare(
+        &mut self,
+        parsed: &ParsedUpdateData,
+        data: &ChroniclerGameUpdateData,
+    ) -> Vec<String> {
+        self.apply(parsed, &GameRules::from_update(data));
+        let observed = Self::from_update(data);
+        let errors = self.differences(&observed);
+        *self = observed;
+        errors
+    }
+
+    // Every sequence of `gap` missing updates that, followed by `current`,
+    // gets from this state to `observed`, which is Chron's state after
+    // `current`. Runners are only compared when we still know where they are.
+    pub fn gap_candidates(
+        &self,
+        gap: usize,
+        current: &ParsedUpdateData,
+        rules: &GameRules,
+        observed: &Self,
+    ) -> Vec<Vec<ParsedUpdateData<'static>>> {
+        iter::repeat_n(GAP_CANDIDATES.iter().cloned(), gap)
+            .multi_cartesian_product()
+            .filter(|missing| {
+                let mut state = self.clone();
+                for update in missing.iter().chain(iter::once(current)) {
+                    state.apply(update, rules);
+                }
+                state.differences(observed).is_empty()
+            })
+            .collect()
+    }
+
+    // The rules for whichever half-inning this state is in
+    pub fn rules(&self, rules: &GameRules) -> HalfInningRules {
+        if self.top_of_inning {
+            rules.top
+        } else {
+            rules.bottom
+        }
+    }
+
+    // `advance`, with the rules for the half-inning we're in
+    pub fn apply(&mut self, parsed: &ParsedUpdateData, rules: &GameRules) {
+        let rules = self.rules(rules);
+        self.advance(parsed, &rules);
+    }
+
+    pub fn batter_count(&self, rules: &HalfInningRules) -> i64 {
+        if rules.away_batting {
+            self.away_batter_count
+        } else {
+            self.home_batter_count
+        }
+    }
+
+    pub fn advance(&mut self, parsed: &ParsedUpdateData, rules: &HalfInningRules) {
+        match parsed {
+            ParsedUpdateData::Empty
+            | ParsedUpdateData::LetsGo
+            | ParsedUpdateData::PlayBall
+            | ParsedUpdateData::InningEnd
+            | ParsedUpdateData::PitcherChange { .. }
+            | ParsedUpdateData::GameOver
+            | ParsedUpdateData::Party { .. }
+            | ParsedUpdateData::Incineration { .. }
+            | ParsedUpdateData::Peanut { .. }
+            | ParsedUpdateData::Blooddrain { .. }
+            | ParsedUpdateData::BirdsFlavor => {}
+            ParsedUpdateData::InningTurnover => {
+                self.top_of_inning = !self.top_of_inning;
+                self.outs = 0;
+                self.reset_count();
+                self.runners = Some(Vec::new());
+            }
+            ParsedUpdateData::BatterUp => {
+                self.reset_count();
+                *self.batter_count_mut(rules) += 1;
+            }
+            ParsedUpdateData::Ball => self.balls += 1,
+            ParsedUpdateData::FoulBall => {
+                // Fouls can't strike you out
+                if self.strikes + 1 < rules.max_strikes {
+                    self.strikes += 1;
+                }
+            }
+            ParsedUpdateData::StrikeLooking | ParsedUpdateData::StrikeSwinging => self.strikes += 1,
+            ParsedUpdateData::StrikeoutLooking { .. }
+            | ParsedUpdateData::StrikeoutSwinging { .. }
+            | ParsedUpdateData::GroundOut { .. }
+            | ParsedUpdateData::Flyout { .. } => {
+                self.reset_count();
+                self.record_outs(1, rules);
+            }
+            ParsedUpdateData::Sacrifice {
+                advanced, scored, ..
+            } => {
+                self.reset_count();
+                if let Some(runners) = &mut self.runners {
+                    for runner in runners.iter_mut() {
+                        if advanced.contains(&runner.name.as_str()) {
+                            runner.base += 1;
+                        }
+                    }
+                }
+                self.score(scored, rules);
+                self.record_outs(1, rules);
+            }
+            ParsedUpdateData::DoublePlay { scored, .. } => {
+                self.reset_count();
+                // The text doesn't say which runner was out unless there's
+                // only one to choose from
+                match &self.runners {
+                    Some(runners) if runners.len() == 1 => self.runners = Some(Vec::new()),
+                    _ => self.runners = None,
+                }
+                self.score(scored, rules);
+                self.record_outs(2, rules);
+            }
+            ParsedUpdateData::FieldersChoice {
+                batter,
+                runner_out,
+                scored,
+                ..
+            } => {
+                self.reset_count();
+                if let Some(runners) = &mut self.runners {
+                    runners.retain(|runner| runner.name != *runner_out);
+                }
+                // Anyone else on base may or may not have moved up
+                if self.runners.as_ref().is_some_and(|r| !r.is_empty()) {
+                    self.runners = None;
+                }
+                self.score(scored, rules);
+                self.put_batter_on(batter, 0);
+                self.record_outs(1, rules);
+            }
+            ParsedUpdateData::Hit {
+                batter,
+                bases,
+                scored,
+            } => {
+                self.reset_count();
+                if let Some(runners) = &mut self.runners {
+                    for runner in runners.iter_mut() {
+                        runner.base += bases;
+                    }
+                }
+                self.score(scored, rules);
+                // Runners who didn't score could have taken extra bases
+                if self.runners.as_ref().is_some_and(|r| !r.is_empty()) {
+                    self.runners = None;
+                }
+                self.put_batter_on(batter, bases - 1);
+            }
+            ParsedUpdateData::HomeRun { runs, .. } => {
+                self.reset_count();
+                *self.score_mut(rules) += *runs as f64;
+                self.runners = Some(Vec::new());
+            }
+            ParsedUpdateData::Walk { batter, scored }
+            | ParsedUpdateData::HitByPitch { batter, scored } => {
+                self.reset_count();
+                self.force_batter_on(batter);
+                self.score(scored, rules);
+            }
+            ParsedUpdateData::MildPitch {
+                is_walk,
+                runners_advanced,
+                scored,
+            } => {
+                self.balls += 1;
+                if *runners_advanced {
+                    if let Some(runners) = &mut self.runners {
+                        for runner in runners.iter_mut() {
+                            runner.base += 1;
+                        }
+                    }
+                }
+                self.score(scored, rules);
+                if *is_walk {
+                    self.reset_count();
+                    // The update doesn't name the batter, so we can't say
+                    // who's on first
+                    self.runners = None;
+                }
+            }
+            ParsedUpdateData::StolenBase { runner, base } => {
+                if let Some(runners) = &mut self.runners {
+                    if let Some(on_base) = runners.iter_mut().find(|r| r.name == *runner) {
+                        on_base.base = *base;
+                    }
+                }
+                if *base >= rules.num_bases - 1 {
+                    self.score(&[*runner], rules);
+                }
+            }
+            ParsedUpdateData::CaughtStealing { runner, .. } => {
+                if let Some(runners) = &mut self.runners {
+                    runners.retain(|on_base| on_base.name != *runner);
+                }
+                self.record_outs(1, rules);
+            }
+            // These move players around without touching anything we track,
+            // except that a runner can be swapped for someone else
+            ParsedUpdateData::FeedbackSwap { .. } | ParsedUpdateData::ReverbShuffle { .. } => {
+                self.runners = None;
+            }
+        }
+    }
+
+    fn reset_count(&mut self) {
+        self.balls = 0;
+        self.strikes = 0;
+    }
+
+    fn batter_count_mut(&mut self, rules: &HalfInningRules) -> &mut i64 {
+        if rules.away_batting {
+            &mut self.away_batter_count
+        } else {
+            &mut self.home_batter_count
+        }
+    }
+
+    fn score_mut(&mut self, rules: &HalfInningRules) -> &mut f64 {
+        if rules.away_batting {
+            &mut self.away_score
+        } else {
+            &mut self.home_score
+        }
+    }
+
+    // Runs count from the names in the update. Anyone who was moved past
+    // third is off the bases whether or not the update says they scored,
+    // and the score comparison will catch it if they should have.
+    fn score(&mut self, scored: &[&str], rules: &HalfInningRules) {
+        *self.score_mut(rules) += scored.len() as f64;
+        if let Some(runners) = &mut self.runners {
+            runners.retain(|runner| {
+                !scored.contains(&runner.name.as_str()) && runner.base < rules.num_bases - 1
+            });
+        }
+    }
+
+    fn record_outs(&mut self, outs: i64, rules: &HalfInningRules) {
+        self.outs += outs;
+        // Chron resets the outs and clears the bases on the update that
+        // makes the last out, not on the turnover
+        if self.outs >= rules.max_outs {
+            self.outs = 0;
+            self.reset_count();
+            self.runners = Some(Vec::new());
+        }
+    }
+
+    fn put_batter_on(&mut self, batter: &str, base: i64) {
+        if let Some(runners) = &mut self.runners {
+            runners.push(TrackedRunner {
+                base,
+                name: batter.to_string(),
+            });
+        }
+    }
+
+    // The batter takes first and pushes up anyone in the way
+    fn force_batter_on(&mut self, batter: &str) {
+        if let Some(runners) = &mut self.runners {
+            for (forced_base, runner) in runners.iter_mut().sorted_by_key(|r| r.base).enumerate() {
+                if runner.base != forced_base as i64 {
+                    break;
+                }
+                runner.base += 1;
+            }
+        }
+        self.put_batter_on(batter, 0);
+    }
+
+    fn differences(&self, observed: &Self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut compare = |what: &str, mine: String, chron: String| {
+            if mine != chron {
+                errors.push(format!(
+                    "Tracked {what} is {mine}, but Chronicler says {chron}"
+                ));
+            }
+        };
+        compare(
+            "half-inning",
+            describe_half(self.top_of_inning).to_string(),
+            describe_half(observed.top_of_inning).to_string(),
+        );
+        compare("balls", self.balls.to_string(), observed.balls.to_string());
+        compare(
+            "strikes",
+            self.strikes.to_string(),
+            observed.strikes.to_string(),
+        );
+        compare("outs", self.outs.to_string(), observed.outs.to_string());
+        compare(
+            "away score",
+            self.away_score.to_string(),
+            observed.away_score.to_string(),
+        );
+        compare(
+            "home score",
+            self.home_score.to_string(),
+            observed.home_score.to_string(),
+        );
+        compare(
+            "away batter count",
+            self.away_batter_count.to_string(),
+            observed.away_batter_count.to_string(),
+        );
+        compare(
+            "home batter count",
+            self.home_batter_count.to_string(),
+            observed.home_batter_count.to_string(),
+        );
+        if let (Some(mine), Some(chron)) = (&self.runners, &observed.runners) {
+            compare("bases", describe_runners(mine), describe_runners(chron));
+        }
+        errors
+    }
+}
+
+fn describe_half(top_of_inning: bool) -> &'static str {
+    if top_of_inning {
+        "the top"
+    } else {
+        "the bottom"
+    }
+}
+
+fn describe_runners(runners: &[TrackedRunner]) -> String {
+    if runners.is_empty() {
+        return "empty".to_string();
+    }
+    runners
+        .iter()
+        .sorted_by_key(|runner| runner.base)
+        .map(|runner| format!("{} on {}", runner.name, runner.base + 1))
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: HalfInningRules = HalfInningRules {
+        away_batting: true,
+        max_strikes: 3,
+        max_outs: 3,
+        num_bases: 4,
+    };
+
+    const GAME_RULES: GameRules = GameRules {
+        top: RULES,
+        bottom: HalfInningRules {
+            away_batting: false,
+            ..RULES
+        },
+    };
+
+    fn state(runners: &[(i64, &str)]) -> TrackedState {
+        TrackedState {
+            top_of_inning: true,
+            balls: 0,
+            strikes: 0,
+            outs: 0,
+            away_score: 0.,
+            home_score: 0.,
+            away_batter_count: 0,
+            home_batter_count: 0,
+            runners: Some(
+                runners
+                    .iter()
+                    .map(|&(base, name)| TrackedRunner {
+                        base,
+                        name: name.to_string(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn walks_force_runners_and_score_them() {
+        let mut tracked = state(&[(0, "A"), (1, "B"), (2, "C")]);
+        tracked.balls = 3;
+        tracked.advance(
+            &ParsedUpdateData::Walk {
+                batter: "D",
+                scored: vec!["C"],
+            },
+            &RULES,
+        );
+
+        let expected = TrackedState {
+            away_score: 1.,
+            ..state(&[(1, "A"), (2, "B"), (0, "D")])
+        };
+        assert_eq!(tracked.differences(&expected), Vec::<String>::new());
+    }
+
+    #[test]
+    fn hits_with_runners_on_lose_track_of_the_bases() {
+        let mut tracked = state(&[(1, "A")]);
+        tracked.advance(
+            &ParsedUpdateData::Hit {
+                batter: "B",
+                bases: 1,
+                scored: Vec::new(),
+            },
+            &RULES,
+        );
+        assert_eq!(tracked.runners, None);
+    }
+
+    #[test]
+    fn a_missed_batter_up_is_the_only_way_to_get_there() {
+        let observed = TrackedState {
+            away_batter_count: 1,
+            balls: 1,
+            ..state(&[])
+        };
+
+        let candidates = state(&[])
+            .gap_candidates(1, &ParsedUpdateData::Ball, &GAME_RULES, &observed)
+            .iter()
+            .map(|missing| describe_gap_update(&missing[0]))
+            .collect_vec();
+        assert_eq!(candidates, vec!["Batter up"]);
+    }
+
+    #[test]
+    fn a_missed_turnover_switches_who_bats() {
+        let observed = TrackedState {
+            top_of_inning: false,
+            home_batter_count: 1,
+            ..state(&[])
+        };
+
+        let candidates = state(&[])
+            .gap_candidates(1, &ParsedUpdateData::BatterUp, &GAME_RULES, &observed)
+            .iter()
+            .map(|missing| describe_gap_update(&missing[0]))
+            .collect_vec();
+        assert_eq!(candidates, vec!["Inning turnover"]);
+    }
+
+    #[test]
+    fn the_last_out_clears_everything() {
+        let mut tracked = state(&[(2, "A")]);
+        tracked.outs = 2;
+        tracked.strikes = 2;
+        tracked.advance(&ParsedUpdateData::StrikeoutSwinging { batter: "B" }, &RULES);
+        assert_eq!(tracked, state(&[]));
+    }
+}
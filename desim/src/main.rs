@@ -1,13 +1,17 @@
 #[macro_use]
 extern crate rocket;
 mod checker;
+mod coverage;
 mod engine;
 mod fragments;
+mod game_state;
 mod nom_helpers;
+mod resync;
 mod rng;
 mod rolls;
 mod sim;
 mod thresholds;
+mod tick_order;
 mod update_parser;
 
 use crate::engine::{Engine, EngineFatalError};
@@ -134,7 +138,11 @@ async fn fragment(
         .take_while(|update| future::ready(update.timestamp < fragment.end_time)));
 
     // Start the engine
-    let mut engine = Engine::new(fragment.rng.clone(), fragment.check_rolls.clone());
+    let mut engine = Engine::new(
+        fragment.rng.clone(),
+        fragment.check_rolls.clone(),
+        fragment.resync.clone(),
+    );
 
     let mut days = Vec::new();
     while let Some(update) = game_updates.next().await {
@@ -147,9 +155,9 @@ async fn fragment(
         );
         if let Some(new_day) = engine.next_update(update, th, &chron).await? {
             days.push(new_day);
-            break; // TEMPORARY only render one day
         }
     }
+    days.extend(engine.finish(th, &chron).await?);
 
     Ok(Template::render(
         "fragment",
@@ -160,8 +168,7 @@ async fn fragment(
     ))
 }
 
-#[launch]
-fn rocket() -> _ {
+fn rocket() -> rocket::Rocket<rocket::Build> {
     let fragments = load_fragments().expect("Failed to load fragments");
     let th = Thresholds::load().expect("Failed to load thresholds");
 
@@ -173,3 +180,18 @@ fn rocket() -> _ {
         .mount("/", routes![error_test, index, fragment])
         .attach(Template::fairing())
 }
+
+#[rocket::main]
+async fn main() {
+    let args = std::env::args().skip(1).collect_vec();
+    if args.first().is_some_and(|command| command == "parser-coverage") {
+        if let Err(err) = coverage::run(&args[1..]).await {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Same as what #[launch] generates. Rocket reports launch errors itself.
+    let _ = rocket().launch().await;
+}
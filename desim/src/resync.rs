@@ -0,0 +1,266 @@
+use crate::fragments::RollStream;
+use crate::rng::Rng;
+use crate::rolls::{infer_outcomes, rolls_for_update};
+use crate::thresholds::Thresholds;
+use crate::{sim, update_parser};
+use blaseball_api::ChroniclerGameUpdate;
+use serde::Deserialize;
+
+// When our rolls stop agreeing with what happened (or we skip an update we
+// couldn't parse), every later roll is garbage. Rather than leave it that way,
+// this tries every Rng position within a window around the current one and
+// follows each of them through the following updates. Positions are thrown out
+// as soon as they contradict an observed outcome, and once the closest
+// surviving one has agreed with enough outcomes the engine jumps to it.
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResyncConfig {
+    // How many rolls forwards and backwards to search
+    pub window: i32,
+    // How many observed outcomes a position has to agree with before we trust
+    // it. Most outcomes are only a coin flip or two of evidence, so this
+    // should be a lot bigger than log2(2 * window + 1).
+    pub outcomes: usize,
+}
+
+struct ResyncCandidate {
+    offset: i32,
+    rng: Rng,
+    // How many rolls this candidate has made since the search started
+    rolls: usize,
+    agreed_outcomes: usize,
+}
+
+pub struct ResyncSearch {
+    candidates: Vec<ResyncCandidate>,
+    // Where the check rolls were when the search started, if there are any
+    check_position: Option<usize>,
+}
+
+pub enum ResyncStatus {
+    Searching,
+    // The Rng to use for the current update, and how far it was from the Rng
+    // the search was started from. The check rolls have to move by
+    // `check_rolls_step` to stay lined up with the new Rng.
+    Found {
+        offset: i32,
+        rng: Rng,
+        check_rolls_step: i64,
+    },
+    Failed,
+}
+
+// What one candidate's rolls for an update had to say, unless they
+// contradicted it
+struct CandidateRolls {
+    rolls: usize,
+    agreed_outcomes: usize,
+}
+
+impl ResyncSearch {
+    pub fn start(rng: &Rng, check_rolls: Option<&RollStream>, config: &ResyncConfig) -> Self {
+        let mut candidates = (-config.window..=config.window)
+            .map(|offset| {
+                let mut rng = rng.clone();
+                rng.step(offset.into());
+                ResyncCandidate {
+                    offset,
+                    rng,
+                    rolls: 0,
+                    agreed_outcomes: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+        // Closest first, so we prefer the smallest correction that works
+        candidates.sort_by_key(|candidate| candidate.offset.abs());
+
+        Self {
+            candidates,
+            check_position: check_rolls.map(RollStream::position),
+        }
+    }
+
+    // Runs every candidate through this update. This must be called before
+    // the update is processed for real, because it needs the game's state
+    // from before the update.
+    pub fn advance(
+        &mut self,
+        game: &sim::Game,
+        update: &ChroniclerGameUpdate,
+        th: &Thresholds,
+        check_rolls: Option<&RollStream>,
+        config: &ResyncConfig,
+    ) -> ResyncStatus {
+        let Ok(parsed_update) = update_parser::parse_update(update) else {
+            // The engine restarts the search after an unparsed update, since
+            // we don't know how many rolls it used
+            return ResyncStatus::Searching;
+        };
+        let game_at_tick = game.at_tick(update);
+
+        self.advance_candidates(check_rolls, config, |rng| {
+            let Ok(mut rolls) = rolls_for_update(rng, parsed_update.clone(), th, &game_at_tick)
+            else {
+                return None;
+            };
+            if !infer_outcomes(&mut rolls).is_empty() {
+                return None;
+            }
+            let mut agreed_outcomes = 0;
+            for roll in &rolls {
+                match roll.agrees_with_outcome() {
+                    Some(true) => agreed_outcomes += 1,
+                    Some(false) => return None,
+                    None => {}
+                }
+            }
+            Some(CandidateRolls {
+                rolls: rolls.len(),
+                agreed_outcomes,
+            })
+        })
+    }
+
+    fn advance_candidates(
+        &mut self,
+        check_rolls: Option<&RollStream>,
+        config: &ResyncConfig,
+        run_update: impl Fn(&mut Rng) -> Option<CandidateRolls>,
+    ) -> ResyncStatus {
+        // The engine has been using check rolls for its own rolls since the
+        // search started, so they're as far along as the engine's Rng is. The
+        // candidate's Rng is `offset + rolls` from the start.
+        let check_rolls_used = self
+            .check_position
+            .zip(check_rolls)
+            .map(|(start, check_rolls)| check_rolls.position() - start);
+
+        let mut found = None;
+        self.candidates.retain_mut(|candidate| {
+            let rng_before_update = candidate.rng.clone();
+            let rolls_before_update = candidate.rolls;
+            let Some(candidate_rolls) = run_update(&mut candidate.rng) else {
+                return false;
+            };
+            candidate.rolls += candidate_rolls.rolls;
+            candidate.agreed_outcomes += candidate_rolls.agreed_outcomes;
+
+            if found.is_none() && candidate.agreed_outcomes >= config.outcomes {
+                let check_rolls_step = check_rolls_used.map_or(0, |used| {
+                    candidate.offset as i64 + rolls_before_update as i64 - used as i64
+                });
+                found = Some(ResyncStatus::Found {
+                    offset: candidate.offset,
+                    rng: rng_before_update,
+                    check_rolls_step,
+                });
+            }
+            true
+        });
+
+        if let Some(found) = found {
+            found
+        } else if self.candidates.is_empty() {
+            ResyncStatus::Failed
+        } else {
+            ResyncStatus::Searching
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fragments::CheckRoll;
+
+    const CONFIG: ResyncConfig = ResyncConfig {
+        window: 8,
+        outcomes: 24,
+    };
+
+    fn rng() -> Rng {
+        Rng::new((11489856334623440466, 7665746933450455135), 0)
+    }
+
+    // Pretends every update is one coin flip whose outcome we observed from
+    // the real Rng. Meanwhile the engine, still on the wrong Rng, uses two
+    // check rolls per update. Returns the final status and how many updates
+    // it took to get there.
+    fn search(real_offset: i32, mut check_rolls: Option<&mut RollStream>) -> (ResyncStatus, usize) {
+        let mut real_rng = rng();
+        real_rng.step(real_offset.into());
+        let mut search = ResyncSearch::start(&rng(), check_rolls.as_deref(), &CONFIG);
+
+        let mut updates = 0;
+        loop {
+            let observed = real_rng.next() < 0.5;
+            let status = search.advance_candidates(check_rolls.as_deref(), &CONFIG, |rng| {
+                ((rng.next() < 0.5) == observed).then_some(CandidateRolls {
+                    rolls: 1,
+                    agreed_outcomes: 1,
+                })
+            });
+            if !matches!(status, ResyncStatus::Searching) {
+                return (status, updates);
+            }
+
+            updates += 1;
+            if let Some(check_rolls) = &mut check_rolls {
+                assert!(check_rolls.step(2));
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_real_rng() {
+        for real_offset in [-5, 0, 3] {
+            let (status, updates) = search(real_offset, None);
+            let ResyncStatus::Found {
+                offset,
+                rng: found_rng,
+                check_rolls_step,
+            } = status
+            else {
+                panic!("Expected to find offset {real_offset}");
+            };
+            assert_eq!(offset, real_offset);
+            assert_eq!(check_rolls_step, 0);
+
+            // The Rng is from just before the update where it was found
+            let mut real_rng = rng();
+            real_rng.step((real_offset + updates as i32).into());
+            assert_eq!(found_rng.state, real_rng.state);
+        }
+    }
+
+    #[test]
+    fn moves_check_rolls_with_the_rng() {
+        let check_roll = serde_json::from_str::<CheckRoll>(
+            r#"{"label": "swing", "roll": 0.5, "passed": null, "threshold": null}"#,
+        )
+        .expect("Check roll should deserialize");
+        let mut check_rolls = RollStream::new(vec![check_roll; 200]);
+        assert!(check_rolls.step(50));
+
+        let real_offset = -3;
+        let (status, updates) = search(real_offset, Some(&mut check_rolls));
+        let ResyncStatus::Found {
+            offset,
+            check_rolls_step,
+            ..
+        } = status
+        else {
+            panic!("Expected to find offset {real_offset}");
+        };
+        assert_eq!(offset, real_offset);
+
+        // The candidate made one roll per update and the engine made two, so
+        // the check rolls have to go back that far plus the offset
+        assert_eq!(check_rolls_step, real_offset as i64 - updates as i64);
+        assert!(check_rolls.step(check_rolls_step));
+        assert_eq!(
+            check_rolls.position(),
+            (50 + real_offset + updates as i32) as usize
+        );
+    }
+}
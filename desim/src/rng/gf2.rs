@@ -0,0 +1,229 @@
+use std::ops::{BitXor, BitXorAssign, Shl, Shr};
+
+// xorshift128+ only ever shifts and xors its state, so every bit of every
+// future (or past) state is the xor of some subset of the 128 bits of the
+// current state. These types track that subset for each bit, which turns
+// questions about the Rng into linear algebra over GF(2).
+//
+// State bit numbering: bits 0..64 are the bits of `Xs128pState.0`, and bits
+// 64..128 are the bits of `Xs128pState.1`.
+
+// One u64 of xs128p state, where each bit is represented by a mask of which
+// state bits it is the xor of
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LinearWord(pub [u128; 64]);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LinearState(pub LinearWord, pub LinearWord);
+
+impl LinearWord {
+    pub fn zero() -> Self {
+        Self([0; 64])
+    }
+
+    // Evaluates this word for a concrete state
+    #[allow(dead_code)] // Only used in tests for now
+    pub fn evaluate(&self, state: u128) -> u64 {
+        self.0.iter().enumerate().fold(0, |word, (bit, mask)| {
+            word | ((((mask & state).count_ones() & 1) as u64) << bit)
+        })
+    }
+}
+
+impl Shl<u32> for LinearWord {
+    type Output = Self;
+
+    fn shl(self, rhs: u32) -> Self::Output {
+        let mut out = Self::zero();
+        for (out_bit, bit) in out.0.iter_mut().skip(rhs as usize).zip(self.0) {
+            *out_bit = bit;
+        }
+        out
+    }
+}
+
+impl Shr<u32> for LinearWord {
+    type Output = Self;
+
+    fn shr(self, rhs: u32) -> Self::Output {
+        let mut out = Self::zero();
+        for (out_bit, bit) in out.0.iter_mut().zip(self.0.into_iter().skip(rhs as usize)) {
+            *out_bit = bit;
+        }
+        out
+    }
+}
+
+impl BitXor for LinearWord {
+    type Output = Self;
+
+    fn bitxor(mut self, rhs: Self) -> Self::Output {
+        self ^= rhs;
+        self
+    }
+}
+
+impl BitXorAssign for LinearWord {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a ^= b;
+        }
+    }
+}
+
+impl LinearState {
+    // The state that every other state is expressed in terms of
+    pub fn identity() -> Self {
+        let mut s0 = LinearWord::zero();
+        let mut s1 = LinearWord::zero();
+        for bit in 0..64 {
+            s0.0[bit] = 1 << bit;
+            s1.0[bit] = 1 << (bit + 64);
+        }
+        Self(s0, s1)
+    }
+
+    // These mirror `xs128p` and `xs128p_rev` exactly. Keep them in sync.
+    pub fn xs128p(self) -> Self {
+        let (mut s1, s0) = (self.0, self.1);
+        s1 ^= s1 << 23;
+        s1 ^= s1 >> 17;
+        s1 ^= s0;
+        s1 ^= s0 >> 26;
+        Self(self.1, s1)
+    }
+
+    pub fn xs128p_rev(self) -> Self {
+        let prev_state1 = self.0;
+        let mut prev_state0 = self.1 ^ (self.0 >> 26);
+        prev_state0 ^= self.0;
+        prev_state0 = prev_state0 ^ (prev_state0 >> 17) ^ (prev_state0 >> 34) ^ (prev_state0 >> 51);
+        prev_state0 = prev_state0 ^ (prev_state0 << 23) ^ (prev_state0 << 46);
+        Self(prev_state0, prev_state1)
+    }
+}
+
+pub fn pack_state(s0: u64, s1: u64) -> u128 {
+    (s0 as u128) | ((s1 as u128) << 64)
+}
+
+pub fn unpack_state(state: u128) -> (u64, u64) {
+    (state as u64, (state >> 64) as u64)
+}
+
+// A system of linear equations over GF(2) in the 128 state bits, kept in
+// reduced row echelon form as equations are added
+pub struct LinearSystem {
+    // (mask, value) pairs. Each mask has a distinct pivot bit (its lowest set
+    // bit) which is not set in any other row.
+    rows: Vec<(u128, bool)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InconsistentSystem;
+
+impl LinearSystem {
+    pub fn new() -> Self {
+        Self { rows: Vec::new() }
+    }
+
+    // Adds the equation `xor of the bits in mask == value`
+    pub fn add(&mut self, mut mask: u128, mut value: bool) -> Result<(), InconsistentSystem> {
+        for &(row_mask, row_value) in &self.rows {
+            let pivot = row_mask & row_mask.wrapping_neg();
+            if mask & pivot != 0 {
+                mask ^= row_mask;
+                value ^= row_value;
+            }
+        }
+
+        if mask == 0 {
+            // The equation was redundant. If it reduced to 0 == 1 it also
+            // contradicted an earlier one.
+            return if value {
+                Err(InconsistentSystem)
+            } else {
+                Ok(())
+            };
+        }
+
+        // Eliminate the new pivot from every existing row to stay reduced
+        let pivot = mask & mask.wrapping_neg();
+        for (row_mask, row_value) in &mut self.rows {
+            if *row_mask & pivot != 0 {
+                *row_mask ^= mask;
+                *row_value ^= value;
+            }
+        }
+        self.rows.push((mask, value));
+        Ok(())
+    }
+
+    pub fn free_bits(&self) -> Vec<u32> {
+        let pivots = self.rows.iter().fold(0u128, |pivots, (mask, _)| {
+            pivots | (mask & mask.wrapping_neg())
+        });
+        (0..128).filter(|bit| pivots & (1 << bit) == 0).collect()
+    }
+
+    // Produces the solution where each free bit takes its value from
+    // `free_values` (bit i of `free_values` goes to the i-th free bit)
+    pub fn solution(&self, free_bits: &[u32], free_values: u64) -> u128 {
+        let free_assignment = free_bits
+            .iter()
+            .enumerate()
+            .fold(0u128, |state, (i, &bit)| {
+                state | ((((free_values >> i) & 1) as u128) << bit)
+            });
+
+        self.rows
+            .iter()
+            .fold(free_assignment, |state, &(mask, value)| {
+                let pivot = mask & mask.wrapping_neg();
+                // Every other bit in the row is free, so its value is already set
+                let rest = ((mask ^ pivot) & free_assignment).count_ones() & 1 == 1;
+                if value ^ rest {
+                    state | pivot
+                } else {
+                    state
+                }
+            })
+    }
+}
+
+// A linear map from one 128-bit state to another. Row i is the mask of input
+// bits that get xored together to make output bit i.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LinearMap(pub [u128; 128]);
+
+impl LinearMap {
+    // The map from the identity state to `state`
+    pub fn from_state(state: LinearState) -> Self {
+        let mut rows = [0; 128];
+        rows[..64].copy_from_slice(&state.0 .0);
+        rows[64..].copy_from_slice(&state.1 .0);
+        Self(rows)
+    }
+
+    pub fn apply(&self, state: u128) -> u128 {
+        self.0.iter().enumerate().fold(0, |out, (bit, row)| {
+            out | ((((row & state).count_ones() & 1) as u128) << bit)
+        })
+    }
+
+    // The map that applies `self` and then `then`
+    pub fn then(&self, then: &Self) -> Self {
+        let mut rows = [0; 128];
+        for (out_row, then_row) in rows.iter_mut().zip(then.0) {
+            // Each output bit of `then` is an xor of outputs of `self`, and
+            // each of those is an xor of inputs
+            *out_row = self
+                .0
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| then_row & (1 << bit) != 0)
+                .fold(0, |row, (_, self_row)| row ^ self_row);
+        }
+        Self(rows)
+    }
+}
@@ -0,0 +1,97 @@
+use super::gf2::{pack_state, unpack_state, LinearMap, LinearState};
+use super::xs128p::Xs128pState;
+use std::sync::OnceLock;
+
+// Stepping xs128p n times is the same as applying its transition matrix n
+// times, which is the same as applying the matrix raised to the nth power.
+// Precomputing the power-of-two powers (in both directions) means any jump
+// is at most 63 matrix applications, no matter how far it goes.
+
+struct JumpTables {
+    // forward[k] steps forward 2^k times, backward[k] steps back 2^k times
+    forward: Vec<LinearMap>,
+    backward: Vec<LinearMap>,
+}
+
+fn powers_of_two(step: LinearMap) -> Vec<LinearMap> {
+    let mut powers = Vec::with_capacity(i64::BITS as usize);
+    powers.push(step);
+    while powers.len() < i64::BITS as usize {
+        let last = powers.last().unwrap();
+        powers.push(last.then(last));
+    }
+    powers
+}
+
+fn jump_tables() -> &'static JumpTables {
+    static TABLES: OnceLock<JumpTables> = OnceLock::new();
+    TABLES.get_or_init(|| JumpTables {
+        forward: powers_of_two(LinearMap::from_state(LinearState::identity().xs128p())),
+        backward: powers_of_two(LinearMap::from_state(LinearState::identity().xs128p_rev())),
+    })
+}
+
+// Equivalent to calling `xs128p` (or `xs128p_rev`, if `steps` is negative)
+// `steps` times
+pub fn jump(state: Xs128pState, steps: i64) -> Xs128pState {
+    let tables = jump_tables();
+    let powers = if steps > 0 {
+        &tables.forward
+    } else {
+        &tables.backward
+    };
+
+    let mut remaining = steps.unsigned_abs();
+    let mut packed = pack_state(state.0, state.1);
+    for power in powers {
+        if remaining == 0 {
+            break;
+        }
+        if remaining & 1 != 0 {
+            packed = power.apply(packed);
+        }
+        remaining >>= 1;
+    }
+
+    let (s0, s1) = unpack_state(packed);
+    Xs128pState(s0, s1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::xs128p::{xs128p, xs128p_rev};
+
+    fn step_slowly(mut state: Xs128pState, steps: i64) -> Xs128pState {
+        for _ in 0..steps.unsigned_abs() {
+            state = if steps > 0 {
+                xs128p(state)
+            } else {
+                xs128p_rev(state)
+            };
+        }
+        state
+    }
+
+    #[test]
+    fn matches_stepping() {
+        let start = Xs128pState(11489856334623440466, 7665746933450455135);
+        for steps in [
+            0, 1, -1, 2, -2, 63, 64, -64, 127, 1000, -1000, 12345, -54321,
+        ] {
+            assert_eq!(
+                jump(start, steps),
+                step_slowly(start, steps),
+                "steps = {steps}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let start = Xs128pState(4278828314640535865, 3539470500018873972);
+        for steps in [1, 1 << 20, i64::MAX, -i64::MAX] {
+            assert_eq!(jump(jump(start, steps), -steps), start, "steps = {steps}");
+        }
+    }
+}
@@ -1,5 +1,9 @@
+mod gf2;
+mod jump;
+pub mod solver;
 mod xs128p;
 
+use jump::jump;
 use nom::Finish;
 use serde::{Deserialize, Deserializer};
 use std::fmt::{Display, Formatter};
@@ -8,6 +12,9 @@ use xs128p::{from_double_bits, from_double_bits_v10, xs128p, xs128p_rev, Xs128pS
 
 type BlockOffset = i32;
 
+// Below this many raw steps it's faster to step one at a time than to jump
+const MIN_JUMP_STEPS: u64 = 256;
+
 // I removed the Copy bound because it's important to know when an Rng object
 // is copied, since the copy and the original won't share state
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -86,17 +93,22 @@ pub fn normalize_offset(offset: BlockOffset) -> BlockOffset {
     return offset.rem_euclid(64);
 }
 
+// Done in i64 so that whole-season jumps, which can be more than 2^31 rolls,
+// don't overflow
 pub fn calculate_steps(
     current_offset: BlockOffset,
-    requested_steps: i32,
-    block_size: i32,
-) -> (i32, i32) {
+    requested_steps: i64,
+    block_size: BlockOffset,
+) -> (i64, BlockOffset) {
+    let current_offset = i64::from(current_offset);
+    let block_size = i64::from(block_size);
     let total_blocks = -(current_offset - requested_steps).div_euclid(block_size);
 
     let block_steps = total_blocks * block_size * 2;
     let total_steps = block_steps - requested_steps;
 
-    let new_offset = (current_offset - requested_steps).rem_euclid(block_size);
+    // rem_euclid by block_size always fits back in a BlockOffset
+    let new_offset = (current_offset - requested_steps).rem_euclid(block_size) as BlockOffset;
 
     (total_steps, new_offset)
 }
@@ -117,8 +129,10 @@ impl Rng {
         (self.state.0, self.state.1, self.offset)
     }
 
-    pub fn step_raw(&mut self, steps: i32) {
-        if steps > 0 {
+    pub fn step_raw(&mut self, steps: i64) {
+        if steps.unsigned_abs() >= MIN_JUMP_STEPS {
+            self.state = jump(self.state, steps);
+        } else if steps > 0 {
             for _ in 0..steps {
                 self.state = xs128p(self.state);
             }
@@ -129,7 +143,7 @@ impl Rng {
         }
     }
 
-    pub fn step(&mut self, steps: i32) {
+    pub fn step(&mut self, steps: i64) {
         let block_size = if self.v10 { 62 } else { 64 };
         let (total_steps, new_offset) = calculate_steps(self.offset, steps, block_size);
         self.step_raw(total_steps);
@@ -163,7 +177,7 @@ impl Rng {
 mod tests {
     use super::*;
 
-    fn step(before: (u64, u64, BlockOffset), steps: BlockOffset) -> (u64, u64, BlockOffset) {
+    fn step(before: (u64, u64, BlockOffset), steps: i64) -> (u64, u64, BlockOffset) {
         let mut rng = Rng::new(Xs128pState(before.0, before.1), before.2);
         rng.step(steps);
         rng.state_tuple()
@@ -264,4 +278,26 @@ mod tests {
             (5559434767711380194, 12515405342771602967, 51)
         );
     }
+
+    #[test]
+    fn raw_step_as_far_as_an_i64_goes() {
+        let start = Xs128pState(11489856334623440466, 7665746933450455135);
+        let mut rng = Rng::new(start, 0);
+        rng.step_raw(i64::MIN);
+        rng.step_raw(i64::MAX);
+        rng.step_raw(1);
+        assert_eq!(rng.state, start);
+    }
+
+    #[test]
+    fn step_past_an_i32() {
+        let start = (11489856334623440466, 7665746933450455135, 59);
+        let far = 3 * (1 << 31) + 5;
+        let there = step(start, far);
+        // Same block offset as stepping 5 would give, since 3 * 2^31 is a
+        // whole number of blocks
+        assert_eq!(there.2, step(start, 5).2);
+        assert_eq!(step(step(start, far - 3000), 3000), there);
+        assert_eq!(step(there, -far), start);
+    }
 }
@@ -0,0 +1,380 @@
+use super::gf2::{unpack_state, LinearState, LinearSystem, LinearWord};
+use super::xs128p::Xs128pState;
+use super::{calculate_steps, BlockOffset, Rng};
+use crate::fragments::CheckRoll;
+use std::collections::HashMap;
+use thiserror::Error;
+
+// Recovers an Rng from things we know about the rolls it produced. Each
+// constraint bounds a roll to a range, and every value in a small enough range
+// shares its leading mantissa bits. Those bits are linear in the starting
+// state (see `gf2`), so with enough constraints we can solve for it directly.
+// This is done once for each of the 64 possible block offsets.
+//
+// This only works for pre-v10 Rngs. v10 rolls add the two state words
+// together, which isn't linear.
+
+// If the constraints leave more unknown bits than this, we don't try to
+// enumerate the possibilities
+const MAX_FREE_BITS: usize = 16;
+
+const BLOCK_SIZE: i32 = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollConstraint {
+    // The roll at `index` was (if `passed`) or was not below `threshold`
+    Threshold {
+        index: usize,
+        threshold: f64,
+        passed: bool,
+    },
+    // The roll at `index` chose option `selected` out of `num_options` (in the
+    // style of `choose_fielder`)
+    Choice {
+        index: usize,
+        num_options: usize,
+        selected: usize,
+    },
+    // The roll at `index` was exactly `value`, like the rolls in a resim roll
+    // stream. Each one pins down the whole mantissa.
+    Exact {
+        index: usize,
+        value: f64,
+    },
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RngSolveError {
+    #[error("No Rng state is consistent with the given constraints")]
+    NoSolution,
+
+    #[error(
+        "The constraints don't determine the Rng state. The best block offset \
+        still had {free_bits} unknown bits."
+    )]
+    Underdetermined { free_bits: usize },
+
+    #[error("{} Rng states are consistent with the given constraints", .0.len())]
+    Ambiguous(Vec<Rng>),
+}
+
+impl RollConstraint {
+    // `index` is how far into the roll stream the roll is
+    pub fn from_check_roll(index: usize, roll: &CheckRoll) -> Self {
+        Self::Exact {
+            index,
+            value: roll.roll,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Threshold { index, .. } => *index,
+            Self::Choice { index, .. } => *index,
+            Self::Exact { index, .. } => *index,
+        }
+    }
+
+    pub fn is_satisfied_by(&self, roll: f64) -> bool {
+        match *self {
+            Self::Threshold {
+                threshold, passed, ..
+            } => (roll < threshold) == passed,
+            Self::Choice {
+                num_options,
+                selected,
+                ..
+            } => (roll * num_options as f64) as usize == selected,
+            Self::Exact { value, .. } => roll == value,
+        }
+    }
+
+    // The range of 52-bit mantissas that satisfy this constraint, inclusive.
+    // This can be very slightly too wide at the edges of a choice, which is
+    // fine because solutions are verified against `is_satisfied_by`.
+    fn mantissa_range(&self) -> Option<(u64, u64)> {
+        const SCALE: f64 = (1u64 << 52) as f64;
+        let (low, high) = match *self {
+            Self::Threshold {
+                threshold, passed, ..
+            } => {
+                let threshold = threshold.clamp(0., 1.);
+                if passed {
+                    (0., threshold)
+                } else {
+                    (threshold, 1.)
+                }
+            }
+            Self::Choice {
+                num_options,
+                selected,
+                ..
+            } => (
+                selected as f64 / num_options as f64,
+                (selected + 1) as f64 / num_options as f64,
+            ),
+            Self::Exact { value, .. } => {
+                // Every roll is a whole number of 2^-52s, so nothing else can
+                // be one
+                let mantissa = value * SCALE;
+                if !(0. ..SCALE).contains(&mantissa) || mantissa.fract() != 0. {
+                    return None;
+                }
+                return Some((mantissa as u64, mantissa as u64));
+            }
+        };
+
+        // Multiplying by a power of two is exact, so these are too
+        let low = (low * SCALE).ceil() as u64;
+        let high = (high * SCALE).ceil() as u64;
+        if high <= low {
+            // Nothing satisfies the constraint
+            return None;
+        }
+        Some((low, high - 1))
+    }
+
+    // Returns each mantissa bit that's shared by every roll that satisfies
+    // this constraint, as (bit index within the mantissa, value)
+    fn known_mantissa_bits(&self) -> Option<Vec<(u32, bool)>> {
+        let (low, high) = self.mantissa_range()?;
+        let num_known = (low ^ high).leading_zeros().saturating_sub(12).min(52);
+        Some(
+            (0..num_known)
+                .map(|i| {
+                    let bit = 51 - i;
+                    (bit, (low >> bit) & 1 == 1)
+                })
+                .collect(),
+        )
+    }
+}
+
+// Returns, for each roll index up to `num_rolls`, how many raw xs128p steps
+// separate it from the starting state when the starting offset is `offset`
+fn raw_positions(offset: BlockOffset, num_rolls: usize) -> Vec<i64> {
+    let mut offset = offset;
+    let mut position = 0;
+    (0..num_rolls)
+        .map(|_| {
+            let (steps, new_offset) = calculate_steps(offset, 1, BLOCK_SIZE);
+            position += steps;
+            offset = new_offset;
+            position
+        })
+        .collect()
+}
+
+// The linear form of `Xs128pState.0` at each requested raw position
+fn linear_s0_at(positions: impl IntoIterator<Item = i64>) -> HashMap<i64, LinearWord> {
+    let mut wanted = positions.into_iter().collect::<Vec<_>>();
+    wanted.sort_unstable();
+    wanted.dedup();
+
+    let mut words = HashMap::with_capacity(wanted.len());
+    // Walk forward from 0 for non-negative positions and backward for
+    // negative ones, so each walk only has to go as far as its furthest
+    // position
+    let (backward, forward) = wanted.split_at(wanted.partition_point(|&p| p < 0));
+    let mut state = LinearState::identity();
+    let mut position = 0;
+    for &target in forward {
+        while position < target {
+            state = state.xs128p();
+            position += 1;
+        }
+        words.insert(target, state.0);
+    }
+    let mut state = LinearState::identity();
+    let mut position = 0;
+    for &target in backward.iter().rev() {
+        while position > target {
+            state = state.xs128p_rev();
+            position -= 1;
+        }
+        words.insert(target, state.0);
+    }
+
+    words
+}
+
+fn satisfies_all(rng: &Rng, constraints: &[RollConstraint]) -> bool {
+    let mut rng = rng.clone();
+    let mut position = 0;
+    constraints.iter().all(|constraint| {
+        rng.step((constraint.index() + 1 - position) as i64);
+        position = constraint.index() + 1;
+        constraint.is_satisfied_by(rng.value())
+    })
+}
+
+enum OffsetSolution {
+    Inconsistent,
+    Underdetermined { free_bits: usize },
+    Candidates(Vec<Rng>),
+}
+
+fn solve_for_offset(
+    offset: BlockOffset,
+    constraints: &[RollConstraint],
+    known_bits: &[Vec<(u32, bool)>],
+) -> OffsetSolution {
+    let num_rolls = constraints.last().map_or(0, |c| c.index() + 1);
+    let positions = raw_positions(offset, num_rolls);
+    let words = linear_s0_at(constraints.iter().map(|c| positions[c.index()]));
+
+    let mut system = LinearSystem::new();
+    for (constraint, bits) in constraints.iter().zip(known_bits) {
+        let word = &words[&positions[constraint.index()]];
+        for &(mantissa_bit, value) in bits {
+            // `from_double_bits` takes the mantissa from `state.0 >> 12`
+            let state_bit = mantissa_bit as usize + 12;
+            if system.add(word.0[state_bit], value).is_err() {
+                return OffsetSolution::Inconsistent;
+            }
+        }
+    }
+
+    let free_bits = system.free_bits();
+    if free_bits.len() > MAX_FREE_BITS {
+        return OffsetSolution::Underdetermined {
+            free_bits: free_bits.len(),
+        };
+    }
+
+    let candidates = (0..1u64 << free_bits.len())
+        .map(|free_values| {
+            let (s0, s1) = unpack_state(system.solution(&free_bits, free_values));
+            Rng::new(Xs128pState(s0, s1), offset)
+        })
+        .filter(|rng| satisfies_all(rng, constraints))
+        .collect();
+
+    OffsetSolution::Candidates(candidates)
+}
+
+// Finds the Rng which, when stepped once, produces the roll at index 0. The
+// constraints don't need to cover every index, but more constraints (and
+// tighter ones) make a unique answer more likely. Roughly 128 known bits are
+// needed.
+pub fn solve_rng(mut constraints: Vec<RollConstraint>) -> Result<Rng, RngSolveError> {
+    constraints.sort_by_key(RollConstraint::index);
+    let known_bits = constraints
+        .iter()
+        .map(RollConstraint::known_mantissa_bits)
+        .collect::<Option<Vec<_>>>()
+        // A constraint that nothing satisfies can't have a solution
+        .ok_or(RngSolveError::NoSolution)?;
+
+    let mut candidates = Vec::new();
+    let mut fewest_free_bits = None;
+    for offset in 0..BLOCK_SIZE {
+        match solve_for_offset(offset, &constraints, &known_bits) {
+            OffsetSolution::Inconsistent => {}
+            OffsetSolution::Underdetermined { free_bits } => {
+                fewest_free_bits =
+                    Some(fewest_free_bits.map_or(free_bits, |f: usize| f.min(free_bits)));
+            }
+            OffsetSolution::Candidates(offset_candidates) => candidates.extend(offset_candidates),
+        }
+    }
+
+    match (candidates.len(), fewest_free_bits) {
+        (1, _) => Ok(candidates.pop().unwrap()),
+        (0, Some(free_bits)) => Err(RngSolveError::Underdetermined { free_bits }),
+        (0, None) => Err(RngSolveError::NoSolution),
+        _ => Err(RngSolveError::Ambiguous(candidates)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::gf2::pack_state;
+    use crate::rng::xs128p::xs128p;
+
+    #[test]
+    fn linear_state_matches_xs128p() {
+        let state = Xs128pState(11489856334623440466, 7665746933450455135);
+        let bits = pack_state(state.0, state.1);
+
+        let mut linear = LinearState::identity();
+        let mut concrete = state;
+        for _ in 0..100 {
+            linear = linear.xs128p();
+            concrete = xs128p(concrete);
+            assert_eq!(
+                (linear.0.evaluate(bits), linear.1.evaluate(bits)),
+                (concrete.0, concrete.1)
+            );
+        }
+
+        for _ in 0..200 {
+            linear = linear.xs128p_rev();
+        }
+        let mut rng = Rng::new(state, 0);
+        rng.step_raw(-100);
+        assert_eq!(
+            Xs128pState(linear.0.evaluate(bits), linear.1.evaluate(bits)),
+            rng.state
+        );
+    }
+
+    #[test]
+    fn solves_from_thresholds_and_choices() {
+        let expected = Rng::new(Xs128pState(11489856334623440466, 7665746933450455135), 59);
+
+        // Mimic a stream of real-ish rolls: mostly coarse thresholds with the
+        // occasional fielder choice
+        let mut rng = expected.clone();
+        let constraints = (0..300)
+            .map(|index| {
+                let roll = rng.next();
+                match index % 3 {
+                    0 => RollConstraint::Choice {
+                        index,
+                        num_options: 9,
+                        selected: (roll * 9.) as usize,
+                    },
+                    1 => RollConstraint::Threshold {
+                        index,
+                        threshold: 0.5,
+                        passed: roll < 0.5,
+                    },
+                    _ => RollConstraint::Threshold {
+                        index,
+                        threshold: 0.25,
+                        passed: roll < 0.25,
+                    },
+                }
+            })
+            .collect();
+
+        assert_eq!(solve_rng(constraints), Ok(expected));
+    }
+
+    #[test]
+    fn too_few_constraints() {
+        let constraints = vec![RollConstraint::Threshold {
+            index: 0,
+            threshold: 0.5,
+            passed: true,
+        }];
+
+        assert!(matches!(
+            solve_rng(constraints),
+            Err(RngSolveError::Underdetermined { .. })
+        ));
+    }
+
+    #[test]
+    fn impossible_constraint() {
+        let constraints = vec![RollConstraint::Threshold {
+            index: 0,
+            threshold: 0.,
+            passed: true,
+        }];
+
+        assert_eq!(solve_rng(constraints), Err(RngSolveError::NoSolution));
+    }
+}
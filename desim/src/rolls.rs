@@ -1,9 +1,11 @@
+use itertools::Itertools;
 use serde::Serialize;
 use std::fmt::{Display, Formatter};
+use thiserror::Error;
 
 use crate::rng::Rng;
-use crate::sim::{GameAtTick, PlayerAtTick};
-use crate::thresholds::Thresholds;
+use crate::sim::{Attribute, GameAtTick, PlayerAtTick, Weather};
+use crate::thresholds::{Threshold, ThresholdTrace, Thresholds};
 use crate::update_parser::{ParsedUpdate, ParsedUpdateData};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -22,10 +24,17 @@ pub enum RollPurpose {
     Double(String),
     Triple(String),
     Steal(i64),
+    StealSuccess,
     Advance((i64, bool)),
+    AdvanceOnOut((i64, String)),
     DoublePlayHappens,
     DoublePlayWhere,
     PartyTargetTeam, // TODO is this right?
+    PartyTargetPlayer,
+    PartyBoost(Attribute),
+    Weather(Weather),
+    PostseasonWeather,
+    OddsFuzzing,
     // This is our escape hatch. Anything we don't support ends up here. Don't
     // ever use this value; instead, add a parser for the thing you're using
     Unparsed(String),
@@ -74,11 +83,17 @@ impl Display for RollPurpose {
                 write!(f, "Was it a triple? (with fielder {name})")
             }
             RollPurpose::Steal(base) => {
-                write!(f, "Did the runner steal base {base}?")
+                write!(f, "Did the runner on base {base} try to steal?")
+            }
+            RollPurpose::StealSuccess => {
+                write!(f, "Did the steal succeed?")
             }
             RollPurpose::Advance(_) => {
                 write!(f, "Did the runner advance?")
             }
+            RollPurpose::AdvanceOnOut((base, name)) => {
+                write!(f, "Did {name} advance from base {base} on the out?")
+            }
             RollPurpose::DoublePlayHappens => {
                 write!(f, "Was there a double play?")
             }
@@ -88,6 +103,21 @@ impl Display for RollPurpose {
             RollPurpose::PartyTargetTeam => {
                 write!(f, "Which team partied?")
             }
+            RollPurpose::PartyTargetPlayer => {
+                write!(f, "Which player partied?")
+            }
+            RollPurpose::PartyBoost(attr) => {
+                write!(f, "How much did the party boost {attr:?}?")
+            }
+            RollPurpose::Weather(weather) => {
+                write!(f, "Did the {weather:?} weather event happen?")
+            }
+            RollPurpose::PostseasonWeather => {
+                write!(f, "Choose the postseason weather")
+            }
+            RollPurpose::OddsFuzzing => {
+                write!(f, "Fuzz the odds")
+            }
             RollPurpose::Unparsed(val) => {
                 write!(f, "Other: {val}")
             }
@@ -95,16 +125,31 @@ impl Display for RollPurpose {
     }
 }
 
+// Where a threshold roll's outcome came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OutcomeSource {
+    // The update told us, or the roll function knew from the update type
+    Update,
+    // A later roll in the same tick only happens with this outcome
+    LaterRoll,
+    // Nothing else pins it down, so it's whatever the roll says. These can't
+    // disagree with the roll, so they don't count as evidence either way.
+    Roll,
+}
+
 #[derive(Serialize)]
 pub enum RollUsage {
     Threshold {
         threshold: Option<f64>,
         passed: Option<bool>,
+        source: OutcomeSource,
     },
     Choice {
         num_options: usize,
         selected_option: Option<usize>,
         selected_option_description: Option<String>,
+        // What the update says was chosen, when it says
+        observed_option_description: Option<String>,
     },
 }
 
@@ -113,21 +158,32 @@ pub struct RollData {
     pub roll: f64,
     pub purpose: RollPurpose,
     pub usage: RollUsage,
+    // How the threshold was worked out. Always None for choices.
+    pub threshold_trace: Option<ThresholdTrace>,
 }
 
 impl RollData {
     pub fn for_threshold(
         rng: &mut Rng,
         purpose: RollPurpose,
-        threshold: Option<f64>,
+        threshold: Option<Threshold>,
         passed: Option<bool>,
     ) -> Self {
         rng.step(1);
+        let (threshold, threshold_trace) = match threshold {
+            Some(Threshold { value, trace }) => (Some(value), Some(trace)),
+            None => (None, None),
+        };
         Self {
             state_string: rng.state_string(),
             roll: rng.value(),
             purpose,
-            usage: RollUsage::Threshold { threshold, passed },
+            usage: RollUsage::Threshold {
+                threshold,
+                passed,
+                source: OutcomeSource::Update,
+            },
+            threshold_trace,
         }
     }
 
@@ -137,6 +193,7 @@ impl RollData {
         num_options: usize,
         selected_option: Option<usize>,
         selected_option_description: Option<String>,
+        observed_option_description: Option<String>,
     ) -> Self {
         rng.step(1);
         Self {
@@ -147,96 +204,353 @@ impl RollData {
                 num_options,
                 selected_option,
                 selected_option_description,
+                observed_option_description,
             },
+            threshold_trace: None,
+        }
+    }
+
+    // Whether the roll value agrees with the outcome we observed. None if we
+    // don't know the threshold or the outcome.
+    pub fn agrees_with_outcome(&self) -> Option<bool> {
+        match &self.usage {
+            RollUsage::Threshold {
+                source: OutcomeSource::Roll,
+                ..
+            } => None,
+            RollUsage::Threshold {
+                threshold: Some(threshold),
+                passed: Some(passed),
+                ..
+            } => Some((self.roll < *threshold) == *passed),
+            RollUsage::Choice {
+                selected_option_description: Some(selected),
+                observed_option_description: Some(observed),
+                ..
+            } => Some(selected == observed),
+            _ => None,
         }
     }
 }
 
-fn party_rolls(rng: &mut Rng, th: &Thresholds) -> Vec<RollData> {
-    let mut rolls = Vec::new();
+// Matches the purpose of an earlier roll, and the outcome that roll must have had
+type EarlierOutcome = (fn(&RollPurpose) -> bool, bool);
+
+// Some rolls only happen after an earlier roll in the same tick passed (or
+// failed). Returns which earlier roll that is and the outcome it must have had.
+fn required_earlier_outcome(purpose: &RollPurpose) -> Option<EarlierOutcome> {
+    match purpose {
+        RollPurpose::PartyTargetTeam => Some((|p| matches!(p, RollPurpose::Party), true)),
+        RollPurpose::StealSuccess => Some((|p| matches!(p, RollPurpose::Steal(_)), true)),
+        RollPurpose::MildPitch => Some((|p| matches!(p, RollPurpose::Weather(_)), false)),
+        RollPurpose::InStrikeZone => Some((|p| matches!(p, RollPurpose::MildPitch), false)),
+        RollPurpose::Contact => Some((|p| matches!(p, RollPurpose::Swing), true)),
+        RollPurpose::FairOrFoul => Some((|p| matches!(p, RollPurpose::Contact), true)),
+        // The out roll passes when it's a hit
+        RollPurpose::HomeRun => Some((|p| matches!(p, RollPurpose::Out(_)), true)),
+        RollPurpose::Fly => Some((|p| matches!(p, RollPurpose::Out(_)), false)),
+        RollPurpose::Double(_) => Some((|p| matches!(p, RollPurpose::HomeRun), false)),
+        RollPurpose::DoublePlayWhere => {
+            Some((|p| matches!(p, RollPurpose::DoublePlayHappens), true))
+        }
+        _ => None,
+    }
+}
+
+// Fills in every threshold roll outcome that the rest of the tick decides.
+// First from the rolls that can only follow a particular outcome, then, for
+// anything still unknown, from the roll value itself. Returns an error for
+// every place the update and the later rolls disagree about an outcome.
+pub fn infer_outcomes(rolls: &mut [RollData]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for i in 0..rolls.len() {
+        let Some((is_earlier, required)) = required_earlier_outcome(&rolls[i].purpose) else {
+            continue;
+        };
+        let (earlier_rolls, rest) = rolls.split_at_mut(i);
+        let Some(earlier) = earlier_rolls
+            .iter_mut()
+            .rev()
+            .find(|roll| is_earlier(&roll.purpose))
+        else {
+            continue;
+        };
+        let RollUsage::Threshold {
+            passed, source, ..
+        } = &mut earlier.usage
+        else {
+            continue;
+        };
+
+        match passed {
+            None => {
+                *passed = Some(required);
+                *source = OutcomeSource::LaterRoll;
+            }
+            Some(passed) if *passed != required => errors.push(format!(
+                "The update says \"{}\" was {}, but \"{}\" can only happen if it {}",
+                earlier.purpose,
+                if *passed { "true" } else { "false" },
+                rest[0].purpose,
+                if required { "passed" } else { "failed" },
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for roll in rolls.iter_mut() {
+        if let RollUsage::Threshold {
+            threshold: Some(threshold),
+            passed: passed @ None,
+            source,
+        } = &mut roll.usage
+        {
+            *passed = Some(roll.roll < *threshold);
+            *source = OutcomeSource::Roll;
+        }
+    }
+
+    errors
+}
 
-    // Party rolls every tick, regardless of party time
-    let party_threshold = th.party();
-    let party_roll = RollData::for_threshold(
+// Every update that isn't a party still rolls for one, and that roll failed
+fn party_rolls(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
+    vec![RollData::for_threshold(
         rng,
         RollPurpose::Party,
-        Some(party_threshold),
-        None, // I'll fill this in when I encounter a party
-    );
-    let party_roll_passed = party_roll.roll < party_threshold;
-    rolls.push(party_roll);
+        Some(th.party(game)),
+        Some(false),
+    )]
+}
+
+// A party replaces everything that would come after the party roll
+fn rolls_for_party(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    player: &str,
+    boosted: &[Attribute],
+) -> Vec<RollData> {
+    let mut rolls = vec![RollData::for_threshold(
+        rng,
+        RollPurpose::Party,
+        Some(th.party(game)),
+        Some(true),
+    )];
+
+    // I'm assuming the options are in away, home order
+    let teams = [
+        ("away", game.game.away_team()),
+        ("home", game.game.home_team()),
+    ];
+    let team_idx = (rng.next_value() * teams.len() as f64) as usize;
+    let observed_team = teams.iter().find(|(_, team)| team.has_player(player));
+    rolls.push(RollData::for_choice(
+        rng,
+        RollPurpose::PartyTargetTeam,
+        teams.len(),
+        Some(team_idx),
+        Some(teams[team_idx].0.to_string()),
+        observed_team.map(|(label, _)| label.to_string()),
+    ));
+
+    // We only know the lineup and current pitcher, not the order of the whole
+    // roster, so this can't say who it picked
+    let (_, party_team) = observed_team.unwrap_or(&teams[team_idx]);
+    rolls.push(RollData::for_choice(
+        rng,
+        RollPurpose::PartyTargetPlayer,
+        party_team.roster_size(),
+        None,
+        None,
+        Some(player.to_string()),
+    ));
 
-    // If party roll passed, a team is chosen
-    if party_roll_passed {
-        rolls.push(RollData::for_choice(
+    // One roll for how much each attribute is boosted, in the order the
+    // update lists them. If it doesn't list them, the Rng is left behind and
+    // that's a job for the resync.
+    for &attr in boosted {
+        rolls.push(RollData::for_threshold(
             rng,
-            RollPurpose::PartyTargetTeam,
-            2,
+            RollPurpose::PartyBoost(attr),
             None,
             None,
-        ))
+        ));
     }
 
     rolls
 }
+
+// A steal the update tells us about
+struct StealAttempt {
+    // The base being stolen, so the runner started on the one before it
+    base: i64,
+    success: bool,
+}
+
+// Every other update implies nobody tried to steal
 fn standard_rolls(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
-    let mut rolls = party_rolls(rng, th);
+    rolls_with_steal(rng, th, game, None)
+}
 
-    let _steal_fielder =
-        choose_fielder_for_purpose(rng, game, &mut rolls, RollPurpose::StealFielder);
+fn rolls_with_steal(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    steal: Option<StealAttempt>,
+) -> Vec<RollData> {
+    let mut rolls = party_rolls(rng, th, game);
+
+    let steal_fielder =
+        choose_fielder_for_purpose(rng, game, &mut rolls, RollPurpose::StealFielder, None);
+
+    let occupied = game.runners_at_start().map(|(base, _)| base).collect_vec();
+    for (current_base, runner) in game.runners_at_start() {
+        // Runners only try to steal when the base ahead of them is empty
+        if occupied.contains(&(current_base + 1)) {
+            continue;
+        }
 
-    for (current_base, _runner) in game.runners_at_start() {
+        let attempt = steal
+            .as_ref()
+            .filter(|steal| steal.base == current_base + 1);
         rolls.push(RollData::for_threshold(
             rng,
             RollPurpose::Steal(current_base),
-            None,
-            None,
+            th.steal_attempt(game, &runner, &steal_fielder),
+            Some(attempt.is_some()),
         ));
+
+        // Only one runner tries to steal per tick, and the success roll only
+        // happens when they do
+        if let Some(attempt) = attempt {
+            rolls.push(RollData::for_threshold(
+                rng,
+                RollPurpose::StealSuccess,
+                th.steal_success(game, &runner, &steal_fielder),
+                Some(attempt.success),
+            ));
+            break;
+        }
     }
 
     rolls
 }
 
-fn rolls_for_pitch(
+fn weather_rolls(
     rng: &mut Rng,
     th: &Thresholds,
     game: &GameAtTick,
-    in_strike_zone: Option<bool>,
+    event_happened: bool,
+) -> Vec<RollData> {
+    let Some(weather) = game.weather.filter(Weather::rolls_every_pitch) else {
+        return vec![];
+    };
+    // The roll happens whether or not we know the weather's chance. Without
+    // one the roll just has no threshold, but we still know how it went.
+    vec![RollData::for_threshold(
+        rng,
+        RollPurpose::Weather(weather),
+        th.weather(weather),
+        Some(event_happened),
+    )]
+}
+
+// Weather events happen instead of a pitch. Whatever the event does after the
+// roll passes isn't modeled yet.
+fn rolls_for_weather_event(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
+    let mut rolls = standard_rolls(rng, th, game);
+    rolls.extend(weather_rolls(rng, th, game, true));
+    rolls
+}
+
+// Everything up to and including the mild pitch roll
+fn pre_pitch_rolls(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    is_mild_pitch: bool,
 ) -> Vec<RollData> {
     let mut rolls = standard_rolls(rng, th, game);
 
+    // If we're here, the weather event didn't happen
+    rolls.extend(weather_rolls(rng, th, game, false));
+
     rolls.push(RollData::for_threshold(
         rng,
         RollPurpose::MildPitch,
-        Some(th.mild_pitch()),
-        Some(false), // I'll fill this in when I encounter a mild pitch
+        Some(th.mild_pitch(game)),
+        Some(is_mild_pitch),
     ));
 
-    rolls.push(RollData::for_threshold(
+    rolls
+}
+
+// A mild pitch is always a ball, so there's no strike zone or swing roll
+// after it. Runners move up without any more rolls.
+fn rolls_for_mild_pitch(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
+    pre_pitch_rolls(rng, th, game, true)
+}
+
+// Returns the rolls along with whether the pitch was in the strike zone,
+// since later rolls depend on it even when the update doesn't tell us
+fn pitch_and_swing_rolls(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    in_strike_zone: Option<bool>,
+    swung: Option<bool>,
+) -> (Vec<RollData>, bool) {
+    let mut rolls = pre_pitch_rolls(rng, th, game, false);
+
+    let zone_threshold = th.in_strike_zone(game);
+    let zone_threshold_value = zone_threshold.value;
+    let zone_roll = RollData::for_threshold(
         rng,
         RollPurpose::InStrikeZone,
-        Some(th.in_strike_zone(game)),
+        Some(zone_threshold),
         in_strike_zone,
-    ));
+    );
+    // Use what the roll says rather than what the update says, so the rest of
+    // the rolls stay consistent with the Rng even if we're out of sync
+    let in_zone = zone_roll.roll < zone_threshold_value;
+    rolls.push(zone_roll);
 
     rolls.push(RollData::for_threshold(
         rng,
         RollPurpose::Swing,
-        None, // TODO
-        None, // TODO
+        Some(th.swing(in_zone, game)),
+        swung,
     ));
 
+    (rolls, in_zone)
+}
+
+fn rolls_for_pitch(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    in_strike_zone: Option<bool>,
+    swung: Option<bool>,
+) -> Vec<RollData> {
+    let (rolls, _) = pitch_and_swing_rolls(rng, th, game, in_strike_zone, swung);
     rolls
 }
 
-fn rolls_for_contact(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
-    let mut rolls = rolls_for_pitch(rng, th, game, None);
+fn rolls_for_contact(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    made_contact: bool,
+) -> Vec<RollData> {
+    let (mut rolls, in_zone) = pitch_and_swing_rolls(rng, th, game, None, Some(true));
 
     rolls.push(RollData::for_threshold(
         rng,
         RollPurpose::Contact,
-        None, // TODO
-        None, // TODO
+        Some(th.contact(in_zone, game)),
+        Some(made_contact),
     ));
 
     rolls
@@ -248,7 +562,7 @@ fn rolls_for_foul_or_fair(
     game: &GameAtTick,
     fair: bool,
 ) -> Vec<RollData> {
-    let mut rolls = rolls_for_contact(rng, th, game);
+    let mut rolls = rolls_for_contact(rng, th, game, true);
 
     rolls.push(RollData::for_threshold(
         rng,
@@ -293,10 +607,18 @@ fn rolls_for_out(
     game: &GameAtTick,
     is_flyout: bool,
     is_dp: bool,
+    scored: &[&str],
+    displayed_fielder: Option<&str>,
 ) -> Vec<RollData> {
     let mut rolls = rolls_for_fair(rng, th, game, false);
 
-    let _fly_fielder = choose_fielder(rng, game, &mut rolls);
+    // Flyouts display the player who caught the ball
+    let fly_fielder = choose_displayed_fielder(
+        rng,
+        game,
+        &mut rolls,
+        displayed_fielder.filter(|_| is_flyout),
+    );
 
     rolls.push(RollData::for_threshold(
         rng,
@@ -305,48 +627,146 @@ fn rolls_for_out(
         Some(is_flyout),
     ));
 
-    if !is_flyout {
-        // Flyouts don't roll displayed fielder again, but ground outs do.
-        // Presumably TBG is picking one player to catch the ball and then, if
-        // it wasn't a flyout, another player to tag the base/runner.
-        let _displayed_fielder = choose_fielder(rng, game, &mut rolls);
+    // Flyouts don't roll displayed fielder again, but ground outs do.
+    // Presumably TBG is picking one player to catch the ball and then, if
+    // it wasn't a flyout, another player to tag the base/runner.
+    if is_flyout {
+        rolls.extend(advance_on_out_rolls(rng, th, game, &fly_fielder, true, scored));
+        return rolls;
     }
+    let out_fielder = choose_displayed_fielder(rng, game, &mut rolls, displayed_fielder);
 
+    // You can't turn two with two outs already
     let eligible_for_double_play =
-        !game.runners_at_start.is_empty() && game.outs + 1 >= game.max_outs;
+        !game.runners_at_start.is_empty() && game.outs_at_start + 1 < game.max_outs;
 
     if eligible_for_double_play {
         rolls.push(RollData::for_threshold(
             rng,
             RollPurpose::DoublePlayHappens,
-            None,
-            None,
+            th.double_play(game, &out_fielder),
+            Some(is_dp),
         ));
 
         if is_dp {
+            // Every runner on at the start is an option, including one on
+            // third who'd be forced home. The runner who scores on a double
+            // play is never the one taken off, so dropping them would only
+            // shift the indices of everyone behind them.
+            let num_runners = game.runners_at_start.len();
+            let selected = (rng.next_value() * num_runners as f64) as usize;
+            let selected_name = game
+                .runners_at_start
+                .get(selected)
+                .and_then(|on_base| game.batting_team().batter_or_runner(on_base.runner_id))
+                .map(|runner| runner.name.clone());
             rolls.push(RollData::for_choice(
                 rng,
                 RollPurpose::DoublePlayWhere,
-                game.runners_at_start.len(), // TODO: Remove forced scores?
-                None,
-                None,
+                num_runners,
+                Some(selected),
+                selected_name,
+                double_play_removed_runner(game, scored),
             ));
+            // Nobody else moves on a double play
+            return rolls;
         }
     }
 
+    rolls.extend(advance_on_out_rolls(rng, th, game, &out_fielder, false, scored));
     rolls
 }
 
+// Tag-ups on flyouts and advances on ground outs. Every runner whose next base
+// is free gets a roll, lead runner first, except that on a ground out the
+// runners forced along from first move up without one.
+fn advance_on_out_rolls(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    fielder: &PlayerAtTick,
+    is_flyout: bool,
+    scored: &[&str],
+) -> Vec<RollData> {
+    let mut rolls = Vec::new();
+
+    let num_forced = if is_flyout {
+        0
+    } else {
+        game.num_forced_runners()
+    };
+
+    // Where the runner ahead of the current one ended up, if they're still on
+    let mut base_ahead = None;
+    // Note this list is already in descending order of occupied base
+    for (base, runner) in game.runners_at_start() {
+        let base_at_end = game
+            .runners_at_end
+            .iter()
+            .find(|r| r.runner_id == runner.player.id)
+            .map(|r| r.base);
+
+        let forced = base < num_forced;
+        let blocked = base_ahead == Some(base + 1);
+        base_ahead = base_at_end;
+        if forced || blocked {
+            continue;
+        }
+
+        // A runner who isn't on base at the end either scored or was put out
+        let advanced = match base_at_end {
+            Some(base_at_end) => Some(base_at_end > base),
+            None if scored.contains(&runner.player.name.as_str()) => Some(true),
+            None => None,
+        };
+
+        rolls.push(RollData::for_threshold(
+            rng,
+            RollPurpose::AdvanceOnOut((base, runner.player.name.clone())),
+            th.advance_on_out(game, &runner, fielder, is_flyout),
+            advanced,
+        ));
+    }
+
+    rolls
+}
+
+// The runner who was put out by a double play is the one who was on base at
+// the start of the tick and isn't on base at the end, without having scored.
+// Returns their index into `runners_at_start` and their name.
+// The runner who was on at the start and isn't any more, without having scored
+fn double_play_removed_runner(game: &GameAtTick, scored: &[&str]) -> Option<String> {
+    game.runners_at_start
+        .iter()
+        .filter(|on_base| {
+            !game
+                .runners_at_end
+                .iter()
+                .any(|at_end| at_end.runner_id == on_base.runner_id)
+        })
+        .find_map(|on_base| {
+            let runner = game.batting_team().batter_or_runner(on_base.runner_id)?;
+            (!scored.contains(&runner.name.as_str())).then(|| runner.name.clone())
+        })
+}
+
 fn rolls_for_basic_out(
     rng: &mut Rng,
     th: &Thresholds,
     game: &GameAtTick,
     is_flyout: bool,
+    scored: &[&str],
+    displayed_fielder: Option<&str>,
 ) -> Vec<RollData> {
-    rolls_for_out(rng, th, game, is_flyout, false)
+    rolls_for_out(rng, th, game, is_flyout, false, scored, displayed_fielder)
 }
 
-fn rolls_for_hit(rng: &mut Rng, th: &Thresholds, game: &GameAtTick, hit_bases: i64, mut scored: Vec<&str>) -> Vec<RollData> {
+fn rolls_for_hit(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    hit_bases: i64,
+) -> Result<Vec<RollData>, RollsError> {
     let mut rolls = rolls_for_fair(rng, th, game, true);
 
     rolls.push(RollData::for_threshold(
@@ -372,26 +792,21 @@ fn rolls_for_hit(rng: &mut Rng, th: &Thresholds, game: &GameAtTick, hit_bases: i
         Some(false),
     ));
 
-    // It's more efficient to pop the last element
-    scored.reverse();
-
-    println!("runners at start:");
-    for (base, runner) in game.runners_at_start() {
-        println!("    {}: {}", base, runner.player.name);
-    }
-
     let mut prev_occupied_base = None;
     // Note this list is already in descending order of occupied base
     for (base, runner) in game.runners_at_start() {
         let base_after_automatic_advance = base + hit_bases;
 
         if let Some(occupied_base) = prev_occupied_base {
-            assert!(occupied_base > base_after_automatic_advance,
-                    "The base this player automatically advanced to was occupied");
+            if occupied_base <= base_after_automatic_advance {
+                return Err(RollsError::AutomaticAdvanceBlocked {
+                    runner: runner.player.name.clone(),
+                    base: base_after_automatic_advance,
+                });
+            }
             if base_after_automatic_advance + 1 == occupied_base {
                 // Then the batter is not eligible to advance
                 prev_occupied_base = Some(base_after_automatic_advance);
-                println!("{} not eligible because {} is occupied", runner.player.name, occupied_base);
                 continue;
             }
         }
@@ -401,35 +816,34 @@ fn rolls_for_hit(rng: &mut Rng, th: &Thresholds, game: &GameAtTick, hit_bases: i
             .iter()
             .find(|r| r.runner_id == runner.player.id)
             .map(|r| r.base);
-        
-        let advanced = if let Some(base_at_end) = base_at_end {
-            if base_at_end == base_after_automatic_advance {
-                false
-            } else if base_at_end == base_after_automatic_advance + 1 {
-                true
-            } else if base_at_end > base_after_automatic_advance {
-                panic!("Batter advanced too much! Was on {base_after_automatic_advance} after the automatic advance, then {base_at_end} after the optional advance!")
-            } else {
-                panic!("Batter advanced a negative amount! Was on {base_after_automatic_advance} after the automatic advance, then {base_at_end} after the optional advance!");
+
+        let advanced = match base_at_end {
+            Some(base_at_end) if base_at_end == base_after_automatic_advance => false,
+            Some(base_at_end) if base_at_end == base_after_automatic_advance + 1 => true,
+            Some(base_at_end) => {
+                return Err(RollsError::ImpossibleAdvance {
+                    runner: runner.player.name.clone(),
+                    after_automatic_advance: base_after_automatic_advance,
+                    at_end: base_at_end,
+                })
             }
-        } else {
-            // Assume the player scored
-            if base_after_automatic_advance >= 3 {
-                // Then they scored from the automatic advance and no roll
-                // is necessary
-                continue;
-            } else {
-                // Then they scored off the optional advance 
-                assert_eq!(base_after_automatic_advance + 1, 3);
-                true
+            // Then they scored from the automatic advance and no roll is
+            // necessary
+            None if base_after_automatic_advance >= 3 => continue,
+            // Then they scored off the optional advance
+            None if base_after_automatic_advance + 1 == 3 => true,
+            None => {
+                return Err(RollsError::ImpossibleScore {
+                    runner: runner.player.name.clone(),
+                    after_automatic_advance: base_after_automatic_advance,
+                })
             }
         };
-        println!("{} advanced: {}", runner.player.name, advanced);
 
         rolls.push(RollData::for_threshold(
             rng,
             RollPurpose::Advance((base_after_automatic_advance, advanced)),
-            Some(th.advance_on_hit(&runner, &fielder)),
+            Some(th.advance_on_hit(game, &runner, &fielder)),
             Some(advanced),
         ));
 
@@ -438,11 +852,37 @@ fn rolls_for_hit(rng: &mut Rng, th: &Thresholds, game: &GameAtTick, hit_bases: i
         }
     }
 
+    Ok(rolls)
+}
+
+// Ball four is rolled just like any other ball. Forced runners move up
+// without any more rolls, so this is the end of the tick.
+fn rolls_for_walk(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
+    rolls_for_pitch(rng, th, game, Some(false), Some(false))
+}
+
+// Everyone on base scores with the batter, so there's nothing left to roll
+// once the home run roll passes
+fn rolls_for_home_run(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
+    let mut rolls = rolls_for_fair(rng, th, game, true);
+
+    rolls.push(RollData::for_threshold(
+        rng,
+        RollPurpose::HomeRun,
+        Some(th.hr(game)),
+        Some(true),
+    ));
+
     rolls
 }
 
-fn rolls_for_double_play(rng: &mut Rng, th: &Thresholds, game: &GameAtTick) -> Vec<RollData> {
-    rolls_for_out(rng, th, game, false, true)
+fn rolls_for_double_play(
+    rng: &mut Rng,
+    th: &Thresholds,
+    game: &GameAtTick,
+    scored: &[&str],
+) -> Vec<RollData> {
+    rolls_for_out(rng, th, game, false, true, scored, None)
 }
 
 fn choose_fielder<'a>(
@@ -450,7 +890,18 @@ fn choose_fielder<'a>(
     game: &'a GameAtTick,
     rolls: &mut Vec<RollData>,
 ) -> PlayerAtTick<'a> {
-    choose_fielder_for_purpose(rng, game, rolls, RollPurpose::Fielder)
+    choose_fielder_for_purpose(rng, game, rolls, RollPurpose::Fielder, None)
+}
+
+// Chooses a fielder whose name appears in the update, so the choice can be
+// checked against it
+fn choose_displayed_fielder<'a>(
+    rng: &mut Rng,
+    game: &'a GameAtTick,
+    rolls: &mut Vec<RollData>,
+    displayed: Option<&str>,
+) -> PlayerAtTick<'a> {
+    choose_fielder_for_purpose(rng, game, rolls, RollPurpose::Fielder, displayed)
 }
 
 fn choose_fielder_for_purpose<'a>(
@@ -458,6 +909,7 @@ fn choose_fielder_for_purpose<'a>(
     game: &'a GameAtTick,
     rolls: &mut Vec<RollData>,
     purpose: RollPurpose,
+    displayed: Option<&str>,
 ) -> PlayerAtTick<'a> {
     let fielder_idx = (rng.next_value() * game.num_fielders() as f64) as usize;
     let fielder = game.fielder(fielder_idx);
@@ -467,35 +919,251 @@ fn choose_fielder_for_purpose<'a>(
         game.num_fielders(),
         Some(fielder_idx),
         Some(fielder.player.name.clone()),
+        displayed.map(str::to_string),
     ));
     fielder
 }
 
+// Checks the runs an update reports against who was on base, for the updates
+// where the state alone decides who scores
+pub fn scoring_errors(data: &ParsedUpdateData, game: &GameAtTick) -> Vec<String> {
+    match data {
+        ParsedUpdateData::HomeRun { runs, .. } => {
+            let expected_runs = game.runners_at_start.len() as i64 + 1;
+            if *runs != expected_runs {
+                vec![format!(
+                    "Home run scored {runs}, but with {} on base it should score {expected_runs}",
+                    game.runners_at_start.len()
+                )]
+            } else {
+                vec![]
+            }
+        }
+        ParsedUpdateData::Walk { scored, .. } => {
+            // A runner is forced home when every base behind them is full
+            let forced_home = (game.num_forced_runners() >= game.num_bases - 1)
+                .then(|| {
+                    game.runners_at_start()
+                        .find(|(base, _)| *base == game.num_bases - 2)
+                        .map(|(_, runner)| runner.player.name.as_str())
+                })
+                .flatten();
+            let expected_scored = forced_home.into_iter().collect_vec();
+            if *scored != expected_scored {
+                vec![format!(
+                    "Walk scored {scored:?}, but it should have forced in {expected_scored:?}"
+                )]
+            } else {
+                vec![]
+            }
+        }
+        _ => vec![],
+    }
+}
+
+// resim picks postseason weather at the start of each postseason game, and
+// then fuzzes the odds. Neither has a threshold or anything to check against.
+fn rolls_for_lets_go(rng: &mut Rng, game: &GameAtTick) -> Vec<RollData> {
+    let mut rolls = Vec::new();
+    if game.is_postseason {
+        rolls.push(RollData::for_threshold(
+            rng,
+            RollPurpose::PostseasonWeather,
+            None,
+            None,
+        ));
+    }
+    rolls.push(RollData::for_threshold(rng, RollPurpose::OddsFuzzing, None, None));
+    rolls
+}
+
+// The update describes something the rolls can't produce. When the update
+// is one of several candidates, the candidate is wrong; otherwise either the
+// update or desim's state is.
+#[derive(Debug, Error)]
+pub enum RollsError {
+    #[error("{runner} would have been pushed to base {base}, but the runner ahead of them is already there")]
+    AutomaticAdvanceBlocked { runner: String, base: i64 },
+
+    #[error("{runner} was on base {after_automatic_advance} after the automatic advance, but ended up on base {at_end}")]
+    ImpossibleAdvance {
+        runner: String,
+        after_automatic_advance: i64,
+        at_end: i64,
+    },
+
+    #[error("{runner} was on base {after_automatic_advance} after the automatic advance, which is too far back to score from")]
+    ImpossibleScore {
+        runner: String,
+        after_automatic_advance: i64,
+    },
+}
+
 pub fn rolls_for_update(
     rng: &mut Rng,
     update: ParsedUpdate,
     th: &Thresholds,
     game: &GameAtTick,
-) -> Vec<RollData> {
-    match update.data {
+) -> Result<Vec<RollData>, RollsError> {
+    let rolls = match update.data {
         // No rolls for these updates
         ParsedUpdateData::Empty => vec![],
+        ParsedUpdateData::LetsGo => rolls_for_lets_go(rng, game),
         ParsedUpdateData::PlayBall => vec![],
         ParsedUpdateData::InningTurnover => vec![],
         ParsedUpdateData::BatterUp => vec![],
         // Balls are known to not be in the strike zone and the player didn't swing
-        ParsedUpdateData::Ball => rolls_for_pitch(rng, th, game, Some(false)),
+        ParsedUpdateData::Ball => rolls_for_pitch(rng, th, game, Some(false), Some(false)),
         // Fouls may be in or out of the strike zone
         ParsedUpdateData::FoulBall => rolls_for_foul(rng, th, game),
         // Strikeouts looking are known to be in the strike zone and the player didn't swing
-        ParsedUpdateData::StrikeLooking => rolls_for_pitch(rng, th, game, Some(true)),
-        ParsedUpdateData::StrikeoutLooking => rolls_for_pitch(rng, th, game, Some(true)),
-        ParsedUpdateData::StrikeSwinging => rolls_for_contact(rng, th, game),
-        ParsedUpdateData::StrikeoutSwinging => rolls_for_contact(rng, th, game),
-        ParsedUpdateData::GroundOut => rolls_for_basic_out(rng, th, game, false),
-        ParsedUpdateData::Flyout => rolls_for_basic_out(rng, th, game, true),
+        ParsedUpdateData::StrikeLooking => rolls_for_pitch(rng, th, game, Some(true), Some(false)),
+        ParsedUpdateData::StrikeoutLooking { .. } => {
+            rolls_for_pitch(rng, th, game, Some(true), Some(false))
+        }
+        // Swinging strikes may be in or out of the strike zone
+        ParsedUpdateData::StrikeSwinging => rolls_for_contact(rng, th, game, false),
+        ParsedUpdateData::StrikeoutSwinging { .. } => rolls_for_contact(rng, th, game, false),
+        ParsedUpdateData::GroundOut { fielder, .. } => {
+            rolls_for_basic_out(rng, th, game, false, &[], Some(fielder))
+        }
+        ParsedUpdateData::Flyout { fielder, .. } => {
+            rolls_for_basic_out(rng, th, game, true, &[], Some(fielder))
+        }
         ParsedUpdateData::InningEnd => vec![],
-        ParsedUpdateData::Hit { bases, scored } => rolls_for_hit(rng, th, game, bases, scored),
-        ParsedUpdateData::DoublePlay => rolls_for_double_play(rng, th, game),
+        ParsedUpdateData::Hit { bases, .. } => rolls_for_hit(rng, th, game, bases)?,
+        ParsedUpdateData::DoublePlay { scored, .. } => {
+            rolls_for_double_play(rng, th, game, &scored)
+        }
+        ParsedUpdateData::Walk { .. } => rolls_for_walk(rng, th, game),
+        ParsedUpdateData::HomeRun { .. } => rolls_for_home_run(rng, th, game),
+        // Steals happen instead of a pitch
+        ParsedUpdateData::StolenBase { base, .. } => rolls_with_steal(
+            rng,
+            th,
+            game,
+            Some(StealAttempt {
+                base,
+                success: true,
+            }),
+        ),
+        ParsedUpdateData::CaughtStealing { base, .. } => rolls_with_steal(
+            rng,
+            th,
+            game,
+            Some(StealAttempt {
+                base,
+                success: false,
+            }),
+        ),
+        ParsedUpdateData::Sacrifice {
+            is_flyout,
+            fielder,
+            scored,
+            ..
+        } => rolls_for_basic_out(rng, th, game, is_flyout, &scored, Some(fielder)),
+        ParsedUpdateData::FieldersChoice { scored, .. } => {
+            rolls_for_basic_out(rng, th, game, false, &scored, None)
+        }
+        // I don't know where the hit by pitch roll goes yet, so this is just a
+        // normal pitch
+        ParsedUpdateData::HitByPitch { .. } => rolls_for_pitch(rng, th, game, None, None),
+        ParsedUpdateData::MildPitch { .. } => rolls_for_mild_pitch(rng, th, game),
+        ParsedUpdateData::PitcherChange { .. } => vec![],
+        ParsedUpdateData::GameOver => vec![],
+        ParsedUpdateData::Party { player, boosted } => {
+            rolls_for_party(rng, th, game, player, &boosted)
+        }
+        ParsedUpdateData::Incineration { .. }
+        | ParsedUpdateData::Peanut { .. }
+        | ParsedUpdateData::FeedbackSwap { .. }
+        | ParsedUpdateData::ReverbShuffle { .. }
+        | ParsedUpdateData::Blooddrain { .. }
+        | ParsedUpdateData::BirdsFlavor => rolls_for_weather_event(rng, th, game),
+    };
+
+    Ok(rolls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold_roll(
+        purpose: RollPurpose,
+        roll: f64,
+        threshold: Option<f64>,
+        passed: Option<bool>,
+    ) -> RollData {
+        RollData {
+            state_string: String::new(),
+            roll,
+            purpose,
+            usage: RollUsage::Threshold {
+                threshold,
+                passed,
+                source: OutcomeSource::Update,
+            },
+            threshold_trace: None,
+        }
+    }
+
+    fn outcome(roll: &RollData) -> (Option<bool>, OutcomeSource) {
+        let RollUsage::Threshold { passed, source, .. } = roll.usage else {
+            panic!("{} should be a threshold roll", roll.purpose);
+        };
+        (passed, source)
+    }
+
+    #[test]
+    fn later_rolls_decide_earlier_outcomes() {
+        // A contact roll means they swung, and a fly roll means it wasn't an out
+        let mut rolls = vec![
+            threshold_roll(RollPurpose::Swing, 0.9, Some(0.5), None),
+            threshold_roll(RollPurpose::Contact, 0.1, Some(0.5), None),
+            threshold_roll(RollPurpose::Out("Jessica".to_string()), 0.1, Some(0.5), None),
+            threshold_roll(RollPurpose::Fly, 0.1, None, None),
+        ];
+
+        let errors = infer_outcomes(&mut rolls);
+
+        assert!(errors.is_empty());
+        assert_eq!(outcome(&rolls[0]), (Some(true), OutcomeSource::LaterRoll));
+        assert_eq!(outcome(&rolls[2]), (Some(false), OutcomeSource::LaterRoll));
+        // So the swing roll disagrees with its threshold, and the out roll too
+        assert_eq!(rolls[0].agrees_with_outcome(), Some(false));
+        assert_eq!(rolls[2].agrees_with_outcome(), Some(false));
+    }
+
+    #[test]
+    fn reports_updates_that_contradict_later_rolls() {
+        let mut rolls = vec![
+            threshold_roll(RollPurpose::Swing, 0.1, Some(0.5), Some(false)),
+            threshold_roll(RollPurpose::Contact, 0.1, Some(0.5), None),
+        ];
+
+        let errors = infer_outcomes(&mut rolls);
+
+        assert_eq!(errors.len(), 1);
+        // The update's outcome stands
+        assert_eq!(outcome(&rolls[0]), (Some(false), OutcomeSource::Update));
+    }
+
+    #[test]
+    fn falls_back_to_the_roll_value() {
+        let mut rolls = vec![
+            threshold_roll(RollPurpose::InStrikeZone, 0.2, Some(0.5), None),
+            threshold_roll(RollPurpose::Swing, 0.8, Some(0.5), None),
+            // Without a threshold there's nothing to fall back on
+            threshold_roll(RollPurpose::Fly, 0.1, None, None),
+        ];
+
+        let errors = infer_outcomes(&mut rolls);
+
+        assert!(errors.is_empty());
+        assert_eq!(outcome(&rolls[0]), (Some(true), OutcomeSource::Roll));
+        assert_eq!(outcome(&rolls[1]), (Some(false), OutcomeSource::Roll));
+        assert_eq!(outcome(&rolls[2]), (None, OutcomeSource::Update));
+        assert!(rolls.iter().all(|roll| roll.agrees_with_outcome() != Some(false)));
     }
 }
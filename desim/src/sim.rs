@@ -1,44 +1,203 @@
+use crate::game_state::{GameRules, TrackedState};
+use crate::update_parser::ParsedUpdateData;
 use blaseball_api::{Chronicler, ChroniclerGameUpdate};
 use chrono::{DateTime, Utc};
 use enum_map::{enum_map, Enum, EnumMap};
 use rocket::futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use std::iter;
+use thiserror::Error;
 use uuid::Uuid;
 
 pub struct GameTeam {
+    id: Uuid,
     lineup: Vec<Player>,
+    // Players we've seen on base who aren't in the lineup, like Attractors
+    guests: Vec<Player>,
     pitcher: Player,
+    // Team mods apply to every player on the team
+    mods: Vec<Mod>,
+    // Lineup plus rotation, for Sinking Ship
+    roster_size: usize,
 }
 
 impl GameTeam {
-    pub async fn fetch(team_id: Uuid, at_time: DateTime<Utc>, chron: &Chronicler) -> Self {
+    pub fn roster_size(&self) -> usize {
+        self.roster_size
+    }
+
+    // Only knows about the lineup, guests and the current pitcher
+    pub fn has_player(&self, name: &str) -> bool {
+        self.lineup
+            .iter()
+            .chain(&self.guests)
+            .chain(iter::once(&self.pitcher))
+            .any(|player| player.name == name)
+    }
+
+    // Anyone who can be on base for this team
+    pub fn batter_or_runner(&self, player_id: Uuid) -> Option<&Player> {
+        self.lineup
+            .iter()
+            .chain(&self.guests)
+            .find(|player| player.id == player_id)
+    }
+
+    // `pitcher_id` is the pitcher the game update says is starting, if it says
+    pub async fn fetch(
+        team_id: Uuid,
+        pitcher_id: Option<Uuid>,
+        at_time: DateTime<Utc>,
+        chron: &Chronicler,
+    ) -> Self {
         let team = chron
             .team(team_id, at_time)
             .await
             .expect("TODO: Handle failure to fetch team");
-        let lineup = stream::iter(team.data.lineup)
-            .then(|player_id| Player::fetch(player_id, at_time, chron))
-            .collect()
-            .await;
+        let lineup = Player::fetch_all(&team.data.lineup, at_time, chron).await;
+
+        let pitcher = match pitcher_id {
+            Some(pitcher_id) => Player::fetch(pitcher_id, at_time, chron).await,
+            None => {
+                let rotation = Player::fetch_all(&team.data.rotation, at_time, chron).await;
+                let can_pitch = rotation.iter().map(Player::can_pitch).collect::<Vec<_>>();
+                let index = starting_pitcher_index(team.data.rotation_slot, &can_pitch)
+                    .expect("TODO: Handle a team with nobody in the rotation who can pitch");
+                rotation
+                    .into_iter()
+                    .nth(index)
+                    .expect("starting_pitcher_index must return an index into the rotation")
+            }
+        };
+
+        let mods = mods_from_ids([
+            &team.data.perm_attr,
+            &team.data.seas_attr,
+            &team.data.week_attr,
+            &team.data.game_attr,
+        ]);
+        let roster_size = team.data.lineup.len() + team.data.rotation.len();
+
+        Self {
+            id: team_id,
+            lineup,
+            guests: Vec::new(),
+            pitcher,
+            mods,
+            roster_size,
+        }
+    }
+
+    // Everything but the pitcher, which follows the game updates instead
+    async fn refetch_lineup(&mut self, at_time: DateTime<Utc>, chron: &Chronicler) {
+        let team = chron
+            .team(self.id, at_time)
+            .await
+            .expect("TODO: Handle failure to fetch team");
+        self.lineup = Player::fetch_all(&team.data.lineup, at_time, chron).await;
+        self.mods = mods_from_ids([
+            &team.data.perm_attr,
+            &team.data.seas_attr,
+            &team.data.week_attr,
+            &team.data.game_attr,
+        ]);
+        self.roster_size = team.data.lineup.len() + team.data.rotation.len();
+
+        // Anyone who joined the lineup isn't a guest any more
+        let lineup = &self.lineup;
+        self.guests
+            .retain(|guest| !lineup.iter().any(|player| player.id == guest.id));
+    }
+
+    async fn change_pitcher(
+        &mut self,
+        pitcher_id: Uuid,
+        at_time: DateTime<Utc>,
+        chron: &Chronicler,
+    ) {
+        if self.pitcher.id != pitcher_id {
+            self.pitcher = Player::fetch(pitcher_id, at_time, chron).await;
+        }
+    }
+}
 
-        // Not sure if solidifying the pitcher this early will come back to bite me
-        // TODO This +1 was the quickest way to get the right pitcher loaded for the one game I
-        //   started with, but I don't know why it's here
-        let rotation_index = (team.data.rotation_slot + 1) as usize % team.data.rotation.len();
-        let pitcher_uuid = team.data.rotation[rotation_index];
-        let pitcher = Player::fetch(pitcher_uuid, at_time, chron).await;
+// The fallback for when the game doesn't tell us who's starting. When we fetch
+// the team at the start of the game, `rotation_slot` still points at whoever
+// started its last game, so the starter is the next pitcher after that who can
+// pitch. Shelled and Elsewhere pitchers get skipped without using up a slot.
+// The rotation can change size between games (and seasons), so the slot is
+// wrapped rather than trusted to be in range.
+fn starting_pitcher_index(rotation_slot: i64, can_pitch: &[bool]) -> Option<usize> {
+    let len = can_pitch.len() as i64;
+    if len == 0 {
+        return None;
+    }
+    (1..=len)
+        .map(|offset| (rotation_slot + offset).rem_euclid(len) as usize)
+        .find(|&index| can_pitch[index])
+}
+
+// Only the attributes that threshold formulas use
+pub struct Stadium {
+    pub forwardness: f64,
+    pub grandiosity: f64,
+    pub obtuseness: f64,
+    pub viscosity: f64,
+    pub ominousness: f64,
+    pub fortification: f64,
+    pub elongation: f64,
+    pub inconvenience: f64,
+}
+
+impl Stadium {
+    pub async fn fetch(stadium_id: Uuid, at_time: DateTime<Utc>, chron: &Chronicler) -> Self {
+        let stadium = chron
+            .stadium(stadium_id, at_time)
+            .await
+            .expect("TODO: Handle failure to fetch stadium");
+        assert_eq!(stadium_id, stadium.data.id);
+
+        Self {
+            forwardness: stadium.data.forwardness,
+            grandiosity: stadium.data.grandiosity,
+            obtuseness: stadium.data.obtuseness,
+            viscosity: stadium.data.viscosity,
+            ominousness: stadium.data.ominousness,
+            fortification: stadium.data.fortification,
+            elongation: stadium.data.elongation,
+            inconvenience: stadium.data.inconvenience,
+        }
+    }
 
-        Self { lineup, pitcher }
+    // Games before ballparks existed (and any game without one) act like
+    // they're in a stadium with every attribute at 0.5
+    pub fn no_stadium() -> Self {
+        Self {
+            forwardness: 0.5,
+            grandiosity: 0.5,
+            obtuseness: 0.5,
+            viscosity: 0.5,
+            ominousness: 0.5,
+            fortification: 0.5,
+            elongation: 0.5,
+            inconvenience: 0.5,
+        }
     }
 }
 
 pub struct Game {
     away_team: GameTeam,
     home_team: GameTeam,
+    pub stadium: Stadium,
 
     // We need the previous event's baserunners to compute the rolls for this
     // event, so it needs to be stored on the game
     prev: Option<Vec<RunnerOnBase>>,
+    // Same idea, for the checks that compare an update with the one before it
+    last_update: Option<UpdateCounters>,
+    // Our own idea of the game state, to check Chron's against. None until
+    // the first update has been processed, since that's where it starts from.
+    state: Option<TrackedState>,
 }
 
 impl Game {
@@ -46,19 +205,46 @@ impl Game {
         first_update: &ChroniclerGameUpdate,
         chron: &Chronicler,
     ) -> Self {
-        let away_team =
-            GameTeam::fetch(first_update.data.away_team, first_update.timestamp, chron).await;
-        let home_team =
-            GameTeam::fetch(first_update.data.home_team, first_update.timestamp, chron).await;
+        let away_team = GameTeam::fetch(
+            first_update.data.away_team,
+            first_update.data.away_pitcher,
+            first_update.timestamp,
+            chron,
+        )
+        .await;
+        let home_team = GameTeam::fetch(
+            first_update.data.home_team,
+            first_update.data.home_pitcher,
+            first_update.timestamp,
+            chron,
+        )
+        .await;
+        let stadium = match first_update.data.stadium_id {
+            Some(stadium_id) => Stadium::fetch(stadium_id, first_update.timestamp, chron).await,
+            None => Stadium::no_stadium(),
+        };
         Game {
             away_team,
             home_team,
+            stadium,
             prev: None,
+            last_update: None,
+            state: None,
         }
     }
 
-    pub fn at_tick<'a>(&'a mut self, game_update: &'a ChroniclerGameUpdate) -> GameAtTick<'a> {
-        let prev = self.prev.take();
+    pub fn away_team(&self) -> &GameTeam {
+        &self.away_team
+    }
+
+    pub fn home_team(&self) -> &GameTeam {
+        &self.home_team
+    }
+
+    // This doesn't consume `prev`, because the tick order solver needs to
+    // build a `GameAtTick` for every candidate ordering before committing to
+    // one. Call `set_prev` once the tick is actually processed.
+    pub fn at_tick<'a>(&'a self, game_update: &'a ChroniclerGameUpdate) -> GameAtTick<'a> {
         let half = if game_update.data.top_of_inning {
             HalfInning::Top
         } else {
@@ -66,38 +252,304 @@ impl Game {
         };
         GameAtTick {
             game: self,
+            season: game_update.data.season,
             day: game_update.data.day,
+            is_postseason: game_update.data.is_postseason,
             half,
             batter_count: match half {
                 HalfInning::Top => game_update.data.away_team_batter_count,
                 HalfInning::Bottom => game_update.data.home_team_batter_count,
             },
-            runners_at_start: prev.unwrap_or_default(),
+            runners_at_start: self.prev.clone().unwrap_or_default(),
             runners_at_end: iter::zip(
                 &game_update.data.bases_occupied,
                 &game_update.data.base_runners,
             )
             .map(|(&base, &runner_id)| RunnerOnBase { base, runner_id })
             .collect(),
+            outs_at_start: self.last_update.as_ref().map_or(0, |last| last.outs),
             outs: game_update.data.half_inning_outs,
+            weather: Weather::from_id(game_update.data.weather),
             max_outs: match half {
                 HalfInning::Top => game_update.data.away_outs,
                 HalfInning::Bottom => game_update.data.home_outs,
             },
+            num_bases: match half {
+                HalfInning::Top => game_update.data.away_bases,
+                HalfInning::Bottom => game_update.data.home_bases,
+            },
         }
     }
 
-    pub fn set_prev(&mut self, prev: Vec<RunnerOnBase>) {
+    pub fn set_prev(&mut self, prev: Vec<RunnerOnBase>, game_update: &ChroniclerGameUpdate) {
         self.prev = Some(prev);
+        self.last_update = Some(UpdateCounters {
+            play_count: game_update.data.play_count,
+            repeat_count: game_update.data.repeat_count,
+            weather: game_update.data.weather,
+            outs: game_update.data.half_inning_outs,
+        });
+    }
+
+    // How many updates Chron skipped between the last one we processed and
+    // this one
+    pub fn missing_updates(&self, game_update: &ChroniclerGameUpdate) -> i64 {
+        self.last_update.as_ref().map_or(0, |last| {
+            (game_update.data.play_count - last.play_count - 1).max(0)
+        })
+    }
+
+    pub fn tracked_state(&self) -> Option<&TrackedState> {
+        self.state.as_ref()
+    }
+
+    // The state for an update Chron missed. It's the next update's state, but
+    // with the half-inning, batter and outs we had at that point.
+    // Reconstructed updates never move runners, but the last out of a
+    // half-inning does clear them.
+    pub fn at_missing_tick<'a>(
+        &'a self,
+        next_update: &'a ChroniclerGameUpdate,
+        state: &TrackedState,
+    ) -> GameAtTick<'a> {
+        let rules = state.rules(&GameRules::from_update(&next_update.data));
+        let mut game_at_tick = self.at_tick(next_update);
+        game_at_tick.half = if state.top_of_inning {
+            HalfInning::Top
+        } else {
+            HalfInning::Bottom
+        };
+        game_at_tick.batter_count = state.batter_count(&rules);
+        game_at_tick.outs_at_start = state.outs;
+        game_at_tick.outs = state.outs;
+        game_at_tick.max_outs = rules.max_outs;
+        game_at_tick.num_bases = rules.num_bases;
+        if state.runners.as_ref().is_some_and(|runners| runners.is_empty()) {
+            game_at_tick.runners_at_start.clear();
+        }
+        game_at_tick.runners_at_end = game_at_tick.runners_at_start.clone();
+        game_at_tick
+    }
+
+    // Once we've decided what the missing updates were, they count as
+    // processed
+    pub fn fill_gap(&mut self, missing: &[ParsedUpdateData], next_update: &ChroniclerGameUpdate) {
+        let rules = GameRules::from_update(&next_update.data);
+        if let Some(state) = &mut self.state {
+            for update in missing {
+                state.apply(update, &rules);
+            }
+        }
+        if let Some(last) = &mut self.last_update {
+            last.play_count = next_update.data.play_count - 1;
+            if let Some(state) = &self.state {
+                last.outs = state.outs;
+            }
+        }
+    }
+
+    // Returns every way the tracked state disagrees with the update. With no
+    // parsed update there's nothing to track, so it just takes Chron's word.
+    pub fn track_state(
+        &mut self,
+        parsed: Option<&ParsedUpdateData>,
+        game_update: &ChroniclerGameUpdate,
+    ) -> Vec<String> {
+        match (&mut self.state, parsed) {
+            (Some(state), Some(parsed)) => state.advance_and_compare(parsed, &game_update.data),
+            _ => {
+                self.state = Some(TrackedState::from_update(&game_update.data));
+                Vec::new()
+            }
+        }
+    }
+
+    // Picks up any pitcher change from an update that's just been processed,
+    // so it applies from the next tick on. That way the update that made the
+    // change is still checked against the pitcher it was rolled with.
+    pub async fn update_pitchers(&mut self, update: &ChroniclerGameUpdate, chron: &Chronicler) {
+        let at_time = update.timestamp;
+        if let Some(pitcher_id) = update.data.away_pitcher {
+            self.away_team.change_pitcher(pitcher_id, at_time, chron).await;
+        }
+        if let Some(pitcher_id) = update.data.home_pitcher {
+            self.home_team.change_pitcher(pitcher_id, at_time, chron).await;
+        }
+    }
+
+    // Same idea as `update_pitchers`, but for everyone else. Feedback, Reverb
+    // and shadow swaps can change the lineups without putting anyone new on
+    // base, so they're spotted from the text. A false positive only costs a
+    // refetch. Anything else that brings in a new player (Attractors, mostly)
+    // shows up as a runner we don't know.
+    pub async fn update_lineups(&mut self, update: &ChroniclerGameUpdate, chron: &Chronicler) {
+        let at_time = update.timestamp;
+        let batting_team_is_away = update.data.top_of_inning;
+        let unknown_runners = |game: &Game| {
+            let batting_team = if batting_team_is_away {
+                &game.away_team
+            } else {
+                &game.home_team
+            };
+            update
+                .data
+                .base_runners
+                .iter()
+                .copied()
+                .filter(|&runner_id| batting_team.batter_or_runner(runner_id).is_none())
+                .collect::<Vec<_>>()
+        };
+
+        let text_changes_lineups = LINEUP_CHANGE_PHRASES
+            .iter()
+            .any(|phrase| update.data.last_update.contains(phrase));
+        if text_changes_lineups || !unknown_runners(self).is_empty() {
+            self.away_team.refetch_lineup(at_time, chron).await;
+            self.home_team.refetch_lineup(at_time, chron).await;
+        }
+
+        let unknown_runners = unknown_runners(self);
+        let batting_team = if batting_team_is_away {
+            &mut self.away_team
+        } else {
+            &mut self.home_team
+        };
+        for runner_id in unknown_runners {
+            let guest = Player::fetch(runner_id, at_time, chron).await;
+            batting_team.guests.push(guest);
+        }
     }
 }
 
+// Bits of update text for events that move players between or within lineups
+const LINEUP_CHANGE_PHRASES: [&str; 3] = [
+    // Feedback
+    "Reality flickered",
+    // Reverb
+    "Reverberations",
+    // Shadow swaps
+    "Shadows",
+];
+
+struct UpdateCounters {
+    play_count: i64,
+    repeat_count: i64,
+    weather: i64,
+    outs: i64,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum HalfInning {
     Top,
     Bottom,
 }
 
+// Only the Beta weathers so far, plus Flooding for High Pressure. The names
+// are used as keys in thresholds.json5.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weather {
+    Void,
+    Sunny,
+    Overcast,
+    Rainy,
+    Sandstorm,
+    Snowy,
+    Acidic,
+    SolarEclipse,
+    Glitter,
+    Blooddrain,
+    Peanuts,
+    Birds,
+    Feedback,
+    Reverb,
+    Flooding,
+}
+
+impl Weather {
+    pub fn from_id(id: i64) -> Option<Self> {
+        match id {
+            0 => Some(Weather::Void),
+            1 => Some(Weather::Sunny),
+            2 => Some(Weather::Overcast),
+            3 => Some(Weather::Rainy),
+            4 => Some(Weather::Sandstorm),
+            5 => Some(Weather::Snowy),
+            6 => Some(Weather::Acidic),
+            7 => Some(Weather::SolarEclipse),
+            8 => Some(Weather::Glitter),
+            9 => Some(Weather::Blooddrain),
+            10 => Some(Weather::Peanuts),
+            11 => Some(Weather::Birds),
+            12 => Some(Weather::Feedback),
+            13 => Some(Weather::Reverb),
+            18 => Some(Weather::Flooding),
+            _ => None,
+        }
+    }
+
+    // Whether this weather does a roll every pitch to see if its event happens.
+    // Flooding only rolls when there are runners to sweep, which isn't
+    // modeled yet.
+    pub fn rolls_every_pitch(&self) -> bool {
+        matches!(
+            self,
+            Weather::SolarEclipse
+                | Weather::Blooddrain
+                | Weather::Peanuts
+                | Weather::Birds
+                | Weather::Feedback
+                | Weather::Reverb
+        )
+    }
+}
+
+// Player and team modifications that desim knows how to apply. Everything
+// else is dropped when players and teams are fetched.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mod {
+    Overperforming,
+    Underperforming,
+    Growth,
+    Traveling,
+    HighPressure,
+    SinkingShip,
+    AffinityForCrows,
+    Chunky,
+    Smooth,
+    // These two only matter for who's allowed to pitch
+    Shelled,
+    Elsewhere,
+}
+
+impl Mod {
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "OVERPERFORMING" => Some(Mod::Overperforming),
+            "UNDERPERFORMING" => Some(Mod::Underperforming),
+            "GROWTH" => Some(Mod::Growth),
+            "TRAVELING" => Some(Mod::Traveling),
+            "HIGH_PRESSURE" => Some(Mod::HighPressure),
+            "SINKING_SHIP" => Some(Mod::SinkingShip),
+            "AFFINITY_FOR_CROWS" => Some(Mod::AffinityForCrows),
+            "CHUNKY" => Some(Mod::Chunky),
+            "SMOOTH" => Some(Mod::Smooth),
+            "SHELLED" => Some(Mod::Shelled),
+            "ELSEWHERE" => Some(Mod::Elsewhere),
+            _ => None,
+        }
+    }
+}
+
+fn mods_from_ids<'a>(lists: impl IntoIterator<Item = &'a Vec<String>>) -> Vec<Mod> {
+    lists
+        .into_iter()
+        .flatten()
+        .filter_map(|id| Mod::from_id(id))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
 pub struct RunnerOnBase {
     pub base: i64,
     pub runner_id: Uuid,
@@ -106,15 +558,25 @@ pub struct RunnerOnBase {
 // `Game` is data that's true for the whole game, while GameState may change per tick
 pub struct GameAtTick<'a> {
     pub game: &'a Game,
+    // Zero-indexed, like Chron, so this is one less than the displayed season
+    pub season: i64,
     pub day: i64,
+    pub is_postseason: bool,
     pub half: HalfInning,
     // I found it more convenient to use the batter count, which I can convert into an index into
     // the lineup, rather than the batter ID which I would have to search the lineup for
     pub batter_count: i64,
     pub runners_at_start: Vec<RunnerOnBase>,
     pub runners_at_end: Vec<RunnerOnBase>,
+    // `outs` is after this update, so anything that depends on the outs when
+    // the play started has to use this
+    pub outs_at_start: i64,
     pub outs: i64,
     pub max_outs: i64,
+    // Including home, so this is 4 for a normal game
+    pub num_bases: i64,
+    // None if it's a weather desim doesn't know about yet
+    pub weather: Option<Weather>,
 }
 
 impl<'a> GameAtTick<'a> {
@@ -125,13 +587,30 @@ impl<'a> GameAtTick<'a> {
         }
     }
 
-    pub fn pitcher(&self) -> PlayerAtTick {
+    // Applies the vibes and mod multipliers for this tick
+    fn player_at_tick<'s>(&'s self, player: &'s Player, team: &'s GameTeam) -> PlayerAtTick<'s> {
+        let conditions = MultiplierConditions {
+            is_away: std::ptr::eq(team, &self.game.away_team),
+            day: self.day,
+            weather: self.weather,
+            runners_on_base: !self.runners_at_start.is_empty(),
+            team_roster_size: team.roster_size,
+        };
+        let mods = player.mods.iter().chain(&team.mods).copied();
+
         PlayerAtTick {
-            player: &self.pitching_team().pitcher,
-            vibes: compute_vibes(&self.pitching_team().pitcher, self.day),
+            player,
+            vibes: compute_vibes(player, self.day),
+            multipliers: EnumMap::from_fn(|attr| {
+                attribute_multiplier(attr, mods.clone(), &conditions)
+            }),
         }
     }
 
+    pub fn pitcher(&self) -> PlayerAtTick {
+        self.player_at_tick(&self.pitching_team().pitcher, self.pitching_team())
+    }
+
     pub fn batting_team(&self) -> &'a GameTeam {
         match self.half {
             HalfInning::Top => &self.game.away_team,
@@ -145,9 +624,16 @@ impl<'a> GameAtTick<'a> {
             panic!("I don't know what this means")
         }
         let index = self.batter_count as usize % batting_lineup.len();
+        self.player_at_tick(&batting_lineup[index], self.batting_team())
+    }
+
+    // The batter's own attributes, but boosted by the pitcher's mods. The fly
+    // formula reads suppression off the batter this way.
+    pub fn batter_with_pitcher_multipliers(&self) -> PlayerAtTick<'_> {
+        let batter = self.batter();
         PlayerAtTick {
-            player: &batting_lineup[index],
-            vibes: compute_vibes(&batting_lineup[index], self.day),
+            multipliers: self.pitcher().multipliers,
+            ..batter
         }
     }
 
@@ -157,25 +643,14 @@ impl<'a> GameAtTick<'a> {
 
     pub fn fielder(&self, index: usize) -> PlayerAtTick {
         let player = &self.pitching_team().lineup[index];
-        PlayerAtTick {
-            player,
-            vibes: compute_vibes(player, self.day),
-        }
+        self.player_at_tick(player, self.pitching_team())
     }
     
+    // Runners we don't know are skipped, and `validate` reports them
     fn runners_helper<'s>(&'s self, it: impl IntoIterator<Item = &'s RunnerOnBase>) -> impl Iterator<Item = (i64, PlayerAtTick<'s>)> {
         it.into_iter().flat_map(move |on_base| {
-            let player = &self
-                .batting_team()
-                .lineup
-                .iter()
-                .find(|player| player.id == on_base.runner_id)
-                // This is going to break for Attractors, isn't it...
-                .expect("Every uuid in base_runners must be a Player in the batting team's lineup");
-            let player_at_tick = PlayerAtTick {
-                player,
-                vibes: compute_vibes(player, self.day),
-            };
+            let player = self.batting_team().batter_or_runner(on_base.runner_id)?;
+            let player_at_tick = self.player_at_tick(player, self.batting_team());
 
             Some((on_base.base, player_at_tick))
         })
@@ -185,11 +660,19 @@ impl<'a> GameAtTick<'a> {
         self.runners_helper(&self.runners_at_start)
     }
 
+    // How many runners, counting up from first base, would be forced to move
+    // up if the batter reached first
+    pub fn num_forced_runners(&self) -> i64 {
+        (0..)
+            .take_while(|base| self.runners_at_start.iter().any(|r| r.base == *base))
+            .count() as i64
+    }
+
     pub fn runners_at_start_rev(&self) -> impl Iterator<Item = (i64, PlayerAtTick<'_>)> {
         self.runners_helper(self.runners_at_start.iter().rev())
     }
 
-    fn batter_match_error(&self, update: &ChroniclerGameUpdate) -> Option<String> {
+    fn batter_match_error(&self, update: &ChroniclerGameUpdate) -> Option<ValidationError> {
         let team_batter_count = if update.data.top_of_inning {
             update.data.away_team_batter_count
         } else {
@@ -213,17 +696,20 @@ impl<'a> GameAtTick<'a> {
 
         let computed_batter_id = self.batter().player.id;
         if computed_batter_id != observed_batter_id {
-            Some(format!("Batter did not match! Computed {computed_batter_id:?} but observed {observed_batter_id:?}"))
+            Some(ValidationError::BatterMismatch {
+                computed: computed_batter_id,
+                observed: observed_batter_id,
+            })
         } else {
             None
         }
     }
 
-    fn pitcher_match_error(&self, update: &ChroniclerGameUpdate) -> Option<String> {
+    fn pitcher_match_error(&self, update: &ChroniclerGameUpdate) -> Option<ValidationError> {
         let Some(observed_pitcher_id) = (if update.data.top_of_inning {
             update.data.home_pitcher
         } else {
-            update.data.away_batter
+            update.data.away_pitcher
         }) else {
             // Spurious mismatch -- we never compute a None. Ignore
             return None;
@@ -231,28 +717,167 @@ impl<'a> GameAtTick<'a> {
 
         let computed_pitcher_id = self.pitcher().player.id;
         if computed_pitcher_id != observed_pitcher_id {
-            Some(format!("Pitcher did not match! Computed {computed_pitcher_id:?} but observed {observed_pitcher_id:?}"))
+            Some(ValidationError::PitcherMismatch {
+                computed: computed_pitcher_id,
+                observed: observed_pitcher_id,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn unknown_runner_errors(&self) -> impl Iterator<Item = ValidationError> + '_ {
+        self.runners_at_start
+            .iter()
+            .filter(|on_base| self.batting_team().batter_or_runner(on_base.runner_id).is_none())
+            .map(|on_base| ValidationError::UnknownRunner {
+                runner_id: on_base.runner_id,
+                base: on_base.base,
+            })
+    }
+
+    // Runners we don't know yet are left to `unknown_runner_errors` on the
+    // next tick, since `Game::update_lineups` hasn't had a chance to fetch them
+    fn runner_name_errors<'u>(
+        &'u self,
+        update: &'u ChroniclerGameUpdate,
+    ) -> impl Iterator<Item = ValidationError> + 'u {
+        iter::zip(&update.data.base_runners, &update.data.base_runner_names)
+            .zip(&update.data.bases_occupied)
+            .filter_map(|((&runner_id, observed_name), &base)| {
+                let player = self.batting_team().batter_or_runner(runner_id)?;
+                (player.name != *observed_name).then(|| ValidationError::RunnerNameMismatch {
+                    runner_id,
+                    base,
+                    computed: player.name.clone(),
+                    observed: observed_name.clone(),
+                })
+            })
+    }
+
+    // Only checks mods desim knows about, and only the batter's own. It's a
+    // warning because the mod could have been added after we fetched them.
+    fn batter_mod_warning(&self, update: &ChroniclerGameUpdate) -> Option<ValidationWarning> {
+        if self.batter_count < 0 {
+            return None;
+        }
+        let observed_mod = if update.data.top_of_inning {
+            &update.data.away_batter_mod
         } else {
+            &update.data.home_batter_mod
+        };
+        let known_mod = Mod::from_id(observed_mod)?;
+
+        let batter = self.batter().player;
+        if batter.mods.contains(&known_mod) {
             None
+        } else {
+            Some(ValidationWarning::BatterModMissing {
+                batter: batter.name.clone(),
+                observed: observed_mod.clone(),
+            })
+        }
+    }
+
+    fn continuity_warnings(&self, update: &ChroniclerGameUpdate) -> Vec<ValidationWarning> {
+        let Some(last) = &self.game.last_update else {
+            return Vec::new();
+        };
+        let mut warnings = Vec::new();
+
+        let play_count = update.data.play_count;
+        if play_count > last.play_count + 1 {
+            warnings.push(ValidationWarning::PlayCountGap {
+                last: last.play_count,
+                current: play_count,
+            });
+        } else if play_count <= last.play_count {
+            warnings.push(ValidationWarning::PlayCountStalled {
+                last: last.play_count,
+                current: play_count,
+            });
+        }
+
+        if update.data.repeat_count != last.repeat_count {
+            warnings.push(ValidationWarning::RepeatCountChanged {
+                last: last.repeat_count,
+                current: update.data.repeat_count,
+            });
         }
+
+        if update.data.weather != last.weather {
+            warnings.push(ValidationWarning::WeatherChanged {
+                last: last.weather,
+                current: update.data.weather,
+            });
+        }
+
+        warnings
     }
 
-    pub fn validate(&self, game_update: &ChroniclerGameUpdate) -> (Vec<String>, Vec<String>) {
+    pub fn validate(
+        &self,
+        game_update: &ChroniclerGameUpdate,
+    ) -> (Vec<ValidationError>, Vec<ValidationWarning>) {
         let errors = iter::empty()
             .chain(self.batter_match_error(game_update))
             .chain(self.pitcher_match_error(game_update))
+            .chain(self.unknown_runner_errors())
+            .chain(self.runner_name_errors(game_update))
             .collect();
 
         let warnings = iter::empty()
-            // Insert some warnings here
+            .chain(self.batter_mod_warning(game_update))
+            .chain(self.continuity_warnings(game_update))
             .collect();
 
         (errors, warnings)
     }
 }
 
+// These are serialized as-is so the template can lay each one out itself
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum ValidationError {
+    #[error("Batter did not match! Computed {computed:?} but observed {observed:?}")]
+    BatterMismatch { computed: Uuid, observed: Uuid },
+
+    #[error("Pitcher did not match! Computed {computed:?} but observed {observed:?}")]
+    PitcherMismatch { computed: Uuid, observed: Uuid },
+
+    #[error("Runner {runner_id:?} on base {base} isn't on the batting team, so their rolls are missing")]
+    UnknownRunner { runner_id: Uuid, base: i64 },
+
+    #[error("Runner {runner_id:?} on base {base} should be {computed}, but the update says {observed}")]
+    RunnerNameMismatch {
+        runner_id: Uuid,
+        base: i64,
+        computed: String,
+        observed: String,
+    },
+}
+
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum ValidationWarning {
+    #[error("Update says {batter} has {observed}, but they didn't when we fetched them")]
+    BatterModMissing { batter: String, observed: String },
+
+    #[error("Play count jumped from {last} to {current}, so updates are missing")]
+    PlayCountGap { last: i64, current: i64 },
+
+    #[error("Play count went from {last} to {current}, so this update is out of order or repeated")]
+    PlayCountStalled { last: i64, current: i64 },
+
+    #[error("Repeat count changed from {last} to {current}, and desim doesn't know what that means yet")]
+    RepeatCountChanged { last: i64, current: i64 },
+
+    #[error("Weather changed from {last} to {current} mid-game")]
+    WeatherChanged { last: i64, current: i64 },
+}
+
 // TODO Attributes have numeric ids, those should used as the repr
-#[derive(Debug, Copy, Clone, Enum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Enum, Serialize)]
 pub enum Attribute {
     // Vibes
     Pressurization,
@@ -291,6 +916,7 @@ pub struct Player {
     pub id: Uuid,
     pub name: String,
     attributes: EnumMap<Attribute, f64>,
+    mods: Vec<Mod>,
 }
 
 impl Player {
@@ -302,9 +928,17 @@ impl Player {
         assert_eq!(player_id, player.entity_id);
         assert_eq!(player_id, player.data.id);
 
+        let mods = mods_from_ids([
+            &player.data.perm_attr,
+            &player.data.seas_attr,
+            &player.data.week_attr,
+            &player.data.game_attr,
+        ]);
+
         Self {
             id: player_id,
             name: player.data.name.clone(),
+            mods,
             attributes: enum_map! {
                 Attribute::Pressurization => player.data.pressurization,
                 Attribute::Cinnamon => player.data.cinnamon,
@@ -335,16 +969,36 @@ impl Player {
             },
         }
     }
+
+    async fn fetch_all(
+        player_ids: &[Uuid],
+        at_time: DateTime<Utc>,
+        chron: &Chronicler,
+    ) -> Vec<Self> {
+        stream::iter(player_ids.iter().copied())
+            .then(|player_id| Player::fetch(player_id, at_time, chron))
+            .collect()
+            .await
+    }
+
+    // Whether the rotation can use this player today
+    fn can_pitch(&self) -> bool {
+        !self
+            .mods
+            .iter()
+            .any(|m| matches!(m, Mod::Shelled | Mod::Elsewhere))
+    }
 }
 
 pub struct PlayerAtTick<'a> {
     pub player: &'a Player,
     pub vibes: f64,
+    multipliers: EnumMap<Attribute, f64>,
 }
 
 impl<'a> PlayerAtTick<'a> {
     pub fn attribute(&self, attr: Attribute) -> AttributeValue {
-        AttributeValue::new(attr, self.player.attributes[attr])
+        AttributeValue::new(attr, self.player.attributes[attr], self.multipliers[attr])
     }
 }
 
@@ -352,34 +1006,88 @@ pub struct AttributeValue {
     #[allow(dead_code)] // I know this will be used eventually
     pub attribute: Attribute,
     pub base_value: f64,
+    pub multiplier: f64,
 }
 
 impl AttributeValue {
-    pub fn new(attribute: Attribute, base_value: f64) -> Self {
+    pub fn new(attribute: Attribute, base_value: f64, multiplier: f64) -> Self {
         Self {
             attribute,
             base_value,
+            multiplier,
         }
     }
 
-    fn compute_multiplier(&self) -> f64 {
-        // TODO: All the multiplier code. It's a lot.
-        1.
-    }
-
     pub fn multiplied(&self) -> f64 {
         // Note for when items are implemented: item contribution to attributes
         // does not get multiplied
-        self.base_value * self.compute_multiplier()
+        self.base_value * self.multiplier
     }
 
     pub fn inverse_multiplied(&self) -> f64 {
         // Note for when items are implemented: item contribution to attributes
         // does not get multiplied
-        self.base_value * 1. / self.compute_multiplier()
+        self.base_value * 1. / self.multiplier
     }
 }
 
+// Everything about the situation that decides which mods apply. This follows
+// get_multiplier in resim's formulas.py.
+struct MultiplierConditions {
+    is_away: bool,
+    day: i64,
+    weather: Option<Weather>,
+    runners_on_base: bool,
+    team_roster_size: usize,
+}
+
+fn attribute_multiplier(
+    attr: Attribute,
+    mods: impl IntoIterator<Item = Mod>,
+    conditions: &MultiplierConditions,
+) -> f64 {
+    // Boosts skip the attributes where higher is worse, whichever mod they
+    // come from
+    if matches!(attr, Attribute::Patheticism | Attribute::Tragicness) {
+        return 1.;
+    }
+
+    let mut multiplier = 1.;
+    for m in mods {
+        match m {
+            Mod::Overperforming => multiplier += 0.2,
+            Mod::Underperforming => multiplier -= 0.2,
+            Mod::Growth => multiplier += (0.05 * (conditions.day as f64 / 99.)).min(0.05),
+            Mod::Traveling if conditions.is_away => multiplier += 0.05,
+            Mod::HighPressure
+                if conditions.weather == Some(Weather::Flooding) && conditions.runners_on_base =>
+            {
+                // This checks for any runners, not just the team's own, so it
+                // also boosts pitchers while the other team is on base
+                multiplier += 0.25
+            }
+            Mod::SinkingShip => multiplier += (14. - conditions.team_roster_size as f64) * 0.01,
+            Mod::AffinityForCrows if conditions.weather == Some(Weather::Birds) => {
+                multiplier += 0.5
+            }
+            Mod::Chunky if conditions.weather == Some(Weather::Peanuts) => match attr {
+                Attribute::Musclitude | Attribute::Divinity => multiplier += 1.0,
+                Attribute::GroundFriction => multiplier += 0.5,
+                _ => {}
+            },
+            Mod::Smooth if conditions.weather == Some(Weather::Peanuts) => match attr {
+                Attribute::Musclitude | Attribute::Continuation | Attribute::Laserlikeness => {
+                    multiplier += 1.0
+                }
+                Attribute::GroundFriction => multiplier += 0.5,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    multiplier
+}
+
 //noinspection ALL
 // Corresponds to SIN_PHASES in resim sin_values.py, but with the outer layer
 // converted from a dict to a vec. This means that the first index into this
@@ -5478,3 +6186,143 @@ fn compute_vibes(player: &Player, day: i64) -> f64 {
     let cinnamon = player.attributes[Attribute::Cinnamon];
     0.5 * ((sin_phase - 1.0) * pressurization + (sin_phase + 1.0) * cinnamon)
 }
+
+// Chron-free stand-ins, for tests that need a whole game
+#[cfg(test)]
+impl Player {
+    pub fn average(id: u128, name: String) -> Self {
+        Self {
+            id: Uuid::from_u128(id),
+            name,
+            attributes: EnumMap::from_fn(|_| 0.5),
+            mods: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<'a> PlayerAtTick<'a> {
+    // No vibes, and the same multiplier on every attribute
+    pub fn with_multiplier(player: &'a Player, multiplier: f64) -> Self {
+        Self {
+            player,
+            vibes: 0.,
+            multipliers: EnumMap::from_fn(|_| multiplier),
+        }
+    }
+}
+
+#[cfg(test)]
+impl GameTeam {
+    fn average(id: u128, name: &str) -> Self {
+        Self {
+            id: Uuid::from_u128(id),
+            lineup: (1..=9)
+                .map(|i| Player::average(id + i, format!("{name} Batter {i}")))
+                .collect(),
+            guests: Vec::new(),
+            pitcher: Player::average(id + 10, format!("{name} Pitcher")),
+            mods: Vec::new(),
+            roster_size: 14,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Game {
+    // Two teams of perfectly average players, with no stadium
+    pub fn average() -> Self {
+        Self {
+            away_team: GameTeam::average(100, "Away"),
+            home_team: GameTeam::average(200, "Home"),
+            stadium: Stadium::no_stadium(),
+            prev: None,
+            last_update: None,
+            state: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions(weather: Option<Weather>) -> MultiplierConditions {
+        MultiplierConditions {
+            is_away: true,
+            day: 20,
+            weather,
+            runners_on_base: false,
+            team_roster_size: 14,
+        }
+    }
+
+    #[test]
+    fn multipliers_stack_and_skip_negative_attributes() {
+        let mods = [Mod::Overperforming, Mod::Traveling];
+        let conditions = conditions(None);
+
+        assert_eq!(
+            attribute_multiplier(Attribute::Thwackability, mods, &conditions),
+            1. + 0.2 + 0.05
+        );
+        assert_eq!(
+            attribute_multiplier(Attribute::Patheticism, mods, &conditions),
+            1.
+        );
+    }
+
+    #[test]
+    fn high_pressure_skips_negative_attributes_too() {
+        let mods = [Mod::HighPressure];
+        let conditions = MultiplierConditions {
+            runners_on_base: true,
+            ..conditions(Some(Weather::Flooding))
+        };
+
+        assert_eq!(
+            attribute_multiplier(Attribute::Thwackability, mods, &conditions),
+            1. + 0.25
+        );
+        assert_eq!(
+            attribute_multiplier(Attribute::Tragicness, mods, &conditions),
+            1.
+        );
+    }
+
+    #[test]
+    fn starting_pitcher_follows_the_last_starter() {
+        assert_eq!(starting_pitcher_index(1, &[true; 5]), Some(2));
+        assert_eq!(starting_pitcher_index(4, &[true; 5]), Some(0));
+        // The rotation shrank since the slot was set
+        assert_eq!(starting_pitcher_index(6, &[true; 4]), Some(3));
+        assert_eq!(starting_pitcher_index(0, &[]), None);
+    }
+
+    #[test]
+    fn starting_pitcher_skips_pitchers_who_cant_pitch() {
+        assert_eq!(
+            starting_pitcher_index(1, &[true, true, false, false, true]),
+            Some(4)
+        );
+        assert_eq!(starting_pitcher_index(2, &[false; 5]), None);
+    }
+
+    #[test]
+    fn weather_multipliers_need_their_weather() {
+        let mods = [Mod::AffinityForCrows];
+
+        assert_eq!(
+            attribute_multiplier(Attribute::Divinity, mods, &conditions(None)),
+            1.
+        );
+        assert_eq!(
+            attribute_multiplier(
+                Attribute::Divinity,
+                mods,
+                &conditions(Some(Weather::Birds))
+            ),
+            1.5
+        );
+    }
+}
@@ -1,14 +1,257 @@
 use crate::sim;
-use crate::sim::{Attribute, PlayerAtTick};
-use serde::Deserialize;
+use crate::sim::{Attribute, PlayerAtTick, Weather};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+// Loaded from disk rather than included, so formulas can be tweaked without
+// recompiling
+static THRESHOLDS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/thresholds.json5");
+
+#[derive(Error, Debug)]
+pub enum LoadThresholdsError {
+    #[error("Couldn't read thresholds file: {0}")]
+    FailedToReadThresholdsFile(std::io::Error),
+
+    #[error("Corrupted thresholds file: {0}")]
+    CorruptedThresholdsFile(json5::Error),
+
+    #[error(
+        "Formula sets must cover every season exactly once, starting from season 1 \
+        and with only the last one open-ended. Problem found at season {0}."
+    )]
+    InvalidSeasonRanges(i64),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Thresholds {
+    // Chance of each weather's event happening on a given pitch. Weathers
+    // missing from here still get their roll, just without a threshold.
+    weather: HashMap<Weather, f64>,
+    // Sorted by season and checked for gaps in `load`
+    formulas: Vec<SeasonFormulas>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeasonFormulas {
+    // Displayed (one-indexed) season numbers, inclusive. No `last_season`
+    // means this set applies to every season from `first_season` on.
+    first_season: i64,
+    last_season: Option<i64>,
+
+    party: f64,
+    mild_pitch: MildPitchFormula,
+    in_strike_zone: StrikeZoneFormula,
+    swing_in_zone: SwingInZoneFormula,
+    swing_not_in_zone: SwingNotInZoneFormula,
+    contact_in_zone: ContactInZoneFormula,
+    contact_not_in_zone: ContactNotInZoneFormula,
+    fair_ball: FairBallFormula,
+    out: OutFormula,
+    fly: FlyFormula,
+    hr: HrFormula,
+    double: DoubleFormula,
+    triple: TripleFormula,
+    advance_on_hit: AdvanceFormula,
+    // resim doesn't log the thresholds for these, so nobody knows the
+    // coefficients yet. Until a set has them fitted against real rolls it
+    // should leave them out, and the rolls don't get a threshold.
+    #[serde(default)]
+    tag_up: Option<AdvanceFormula>,
+    #[serde(default)]
+    advance_on_ground_out: Option<AdvanceFormula>,
+    #[serde(default)]
+    steal_attempt: Option<StealFormula>,
+    #[serde(default)]
+    steal_success: Option<StealFormula>,
+    #[serde(default)]
+    double_play: Option<DoublePlayFormula>,
+}
+
+// Each of these mirrors the shape of one formula below, with one field per
+// coefficient. Subtracted terms have negative coefficients.
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MildPitchFormula {
+    intercept: f64,
+    mysticism: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrikeZoneFormula {
+    intercept: f64,
+    pitcher_ruthlessness: f64,
+    stadium_forwardness: f64,
+    batter_musclitude: f64,
+    max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwingInZoneFormula {
+    intercept: f64,
+    combined_batting: f64,
+    pitcher_ruthlessness: f64,
+    stadium_viscosity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwingNotInZoneFormula {
+    pitcher_ruthlessness: f64,
+    batter_moxie: f64,
+    batter_patheticism: f64,
+    stadium_viscosity: f64,
+    divisor: f64,
+    exponent: f64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContactInZoneFormula {
+    intercept: f64,
+    pitcher_ruthlessness: f64,
+    ballpark: f64,
+    combined_batting: f64,
+    combined_batting_exponent: f64,
+    stadium_fortification: f64,
+    stadium_viscosity: f64,
+    stadium_forwardness: f64,
+    ballpark_divisor: f64,
+    max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContactNotInZoneFormula {
+    intercept: f64,
+    pitcher_ruthlessness: f64,
+    batter_patheticism: f64,
+    batter_patheticism_exponent: f64,
+    ballpark: f64,
+    stadium_fortification: f64,
+    stadium_viscosity: f64,
+    stadium_forwardness: f64,
+    ballpark_divisor: f64,
+    max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FairBallFormula {
+    intercept: f64,
+    stadium_forwardness: f64,
+    stadium_obtuseness: f64,
+    combined_batting: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutFormula {
+    intercept: f64,
+    batter_thwackability: f64,
+    pitcher_unthwackability: f64,
+    fielder_omniscience: f64,
+    stadium_grandiosity: f64,
+    stadium_ominousness: f64,
+    stadium_inconvenience: f64,
+    stadium_viscosity: f64,
+    stadium_forwardness: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FlyFormula {
+    intercept: f64,
+    batter_buoyancy: f64,
+    suppression: f64,
+    stadium_ominousness: f64,
+    min: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HrFormula {
+    intercept: f64,
+    batter_divinity: f64,
+    // The pitcher term is a weighted average of overpowerment and suppression
+    pitcher: f64,
+    pitcher_overpowerment: f64,
+    pitcher_suppression: f64,
+    pitcher_divisor: f64,
+    ballpark: f64,
+    stadium_grandiosity: f64,
+    stadium_fortification: f64,
+    stadium_viscosity: f64,
+    stadium_ominousness: f64,
+    stadium_forwardness: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DoubleFormula {
+    intercept: f64,
+    batter_musclitude: f64,
+    pitcher_overpowerment: f64,
+    fielder_chasiness: f64,
+    stadium_forwardness: f64,
+    stadium_elongation: f64,
+    stadium_ominousness: f64,
+    stadium_viscosity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TripleFormula {
+    intercept: f64,
+    batter_ground_friction: f64,
+    pitcher_overpowerment: f64,
+    fielder_chasiness: f64,
+    ballpark: f64,
+    ballpark_divisor: f64,
+    stadium_forwardness: f64,
+    stadium_grandiosity: f64,
+    stadium_obtuseness: f64,
+    stadium_viscosity: f64,
+    stadium_ominousness: f64,
+}
 
-static THRESHOLDS_JSON: &str = include_str!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/resources/thresholds.json5"
-));
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DoublePlayFormula {
+    intercept: f64,
+    batter_laserlikeness: f64,
+    fielder_tenaciousness: f64,
+    min: f64,
+    max: f64,
+}
 
+// Attempt and success use the same terms with different coefficients
 #[derive(Debug, Deserialize)]
-pub struct Thresholds {}
+#[serde(deny_unknown_fields)]
+struct StealFormula {
+    intercept: f64,
+    runner_laserlikeness: f64,
+    fielder_watchfulness: f64,
+    min: f64,
+    max: f64,
+}
+
+// Hits, tag-ups and ground outs all use the same terms
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdvanceFormula {
+    intercept: f64,
+    fielder_tenaciousness: f64,
+    runner_continuation: f64,
+    min: f64,
+    max: f64,
+}
 
 trait Vibable {
     fn vibed(self: &Self, vibes: f64) -> f64;
@@ -20,33 +263,180 @@ impl Vibable for f64 {
     }
 }
 
+// A threshold along with how it was worked out, so a mismatch against resim
+// can be traced back to the term that's off
+#[derive(Debug, Clone)]
+pub struct Threshold {
+    pub value: f64,
+    pub trace: ThresholdTrace,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThresholdTrace {
+    pub terms: Vec<TraceTerm>,
+    // Only set for formulas that clamp
+    pub unclamped: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+// How an attribute's base value was turned into the value the formula used
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Scaling {
+    Base,
+    Multiplied,
+    InverseMultiplied,
+    // One minus the inverse multiplied value. For attributes where higher is
+    // worse, a boost divides the attribute, and this turns what's left into a
+    // higher-is-better term. It's one scaling, not two inversions.
+    InverseMultipliedComplement,
+    // Multiplied, then vibed
+    Vibed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum TraceTerm {
+    Attribute {
+        role: &'static str,
+        attribute: Attribute,
+        base_value: f64,
+        multiplier: f64,
+        scaling: Scaling,
+        vibes: Option<f64>,
+        value: f64,
+    },
+    Stadium {
+        name: &'static str,
+        value: f64,
+    },
+    // Anything else that goes into a formula, like a hard-coded attribute
+    Constant {
+        name: &'static str,
+        value: f64,
+    },
+}
+
+impl ThresholdTrace {
+    fn attribute(
+        &mut self,
+        role: &'static str,
+        player: &PlayerAtTick,
+        attribute: Attribute,
+        scaling: Scaling,
+    ) -> f64 {
+        let attr = player.attribute(attribute);
+        let (value, vibes) = match scaling {
+            Scaling::Base => (attr.base_value, None),
+            Scaling::Multiplied => (attr.multiplied(), None),
+            Scaling::InverseMultiplied => (attr.inverse_multiplied(), None),
+            Scaling::InverseMultipliedComplement => (1. - attr.inverse_multiplied(), None),
+            Scaling::Vibed => (attr.multiplied().vibed(player.vibes), Some(player.vibes)),
+        };
+        self.terms.push(TraceTerm::Attribute {
+            role,
+            attribute,
+            base_value: attr.base_value,
+            multiplier: attr.multiplier,
+            scaling,
+            vibes,
+            value,
+        });
+        value
+    }
+
+    fn stadium(&mut self, name: &'static str, value: f64) -> f64 {
+        self.terms.push(TraceTerm::Stadium { name, value });
+        value
+    }
+
+    fn constant(&mut self, name: &'static str, value: f64) -> f64 {
+        self.terms.push(TraceTerm::Constant { name, value });
+        value
+    }
+
+    // Applies `max` before `min`, which is the order every formula uses
+    fn clamp(&mut self, value: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+        self.unclamped = Some(value);
+        self.min = min;
+        self.max = max;
+        let value = max.map_or(value, |max| value.min(max));
+        min.map_or(value, |min| value.max(min))
+    }
+
+    fn finish(self, value: f64) -> Threshold {
+        Threshold { value, trace: self }
+    }
+}
+
 impl Thresholds {
-    pub fn load() -> Result<Self, json5::Error> {
-        json5::from_str(THRESHOLDS_JSON)
+    pub fn load() -> Result<Self, LoadThresholdsError> {
+        let thresholds_json5 = std::fs::read_to_string(THRESHOLDS_PATH)
+            .map_err(LoadThresholdsError::FailedToReadThresholdsFile)?;
+        let mut thresholds: Self = json5::from_str(&thresholds_json5)
+            .map_err(LoadThresholdsError::CorruptedThresholdsFile)?;
+
+        thresholds.formulas.sort_by_key(|f| f.first_season);
+        let mut next_season = Some(1);
+        for formulas in &thresholds.formulas {
+            match next_season {
+                Some(season) if season == formulas.first_season => {}
+                // Either a gap, an overlap, or something after an open-ended set
+                _ => {
+                    return Err(LoadThresholdsError::InvalidSeasonRanges(
+                        formulas.first_season,
+                    ))
+                }
+            }
+            next_season = formulas.last_season.map(|last| last + 1);
+        }
+        if let Some(season) = next_season {
+            return Err(LoadThresholdsError::InvalidSeasonRanges(season));
+        }
+
+        Ok(thresholds)
+    }
+
+    fn formulas(&self, game: &sim::GameAtTick) -> &SeasonFormulas {
+        // Chron's seasons are zero-indexed
+        self.formulas_for_season(game.season + 1)
     }
 
-    pub fn in_strike_zone(&self, game: &sim::GameAtTick) -> f64 {
-        let pitcher_vibes = game.pitcher().vibes;
+    fn formulas_for_season(&self, season: i64) -> &SeasonFormulas {
+        self.formulas
+            .iter()
+            .rev()
+            .find(|formulas| formulas.first_season <= season)
+            .expect("Formula sets should cover every season")
+    }
+
+    pub fn in_strike_zone(&self, game: &sim::GameAtTick) -> Threshold {
+        let f = &self.formulas(game).in_strike_zone;
+        let mut t = ThresholdTrace::default();
+        let pitcher = game.pitcher();
+        let batter = game.batter();
 
-        let ruthlessness = game
-            .pitcher()
-            .attribute(Attribute::Ruthlessness)
-            .multiplied();
+        let ruthlessness =
+            t.attribute("pitcher", &pitcher, Attribute::Ruthlessness, Scaling::Vibed);
 
-        let musclitude = game.batter().attribute(Attribute::Musclitude).multiplied();
+        let musclitude = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Musclitude,
+            Scaling::Multiplied,
+        );
 
-        let forwardness = 0.5; // Stadium attributes default to 0.5 when there is no stadium
+        let forwardness = t.stadium("forwardness", game.game.stadium.forwardness);
 
-        let threshold = 0.2
-            + 0.35 * (ruthlessness * (1.0 + 0.2 * pitcher_vibes))
-            + 0.2 * forwardness
-            + 0.1 * musclitude;
+        let threshold = f.intercept
+            + f.pitcher_ruthlessness * ruthlessness
+            + f.stadium_forwardness * forwardness
+            + f.batter_musclitude * musclitude;
 
-        threshold.min(0.9)
+        let threshold = t.clamp(threshold, None, Some(f.max));
+        t.finish(threshold)
     }
 
-    #[allow(dead_code)] // TODO Remove once this is called for real
-    pub fn swing(&self, in_zone: bool, game: &sim::GameAtTick) -> f64 {
+    pub fn swing(&self, in_zone: bool, game: &sim::GameAtTick) -> Threshold {
         if in_zone {
             self.swing_on_pitch_in_zone(game)
         } else {
@@ -54,263 +444,572 @@ impl Thresholds {
         }
     }
 
-    pub fn swing_on_pitch_in_zone(&self, game: &sim::GameAtTick) -> f64 {
+    pub fn swing_on_pitch_in_zone(&self, game: &sim::GameAtTick) -> Threshold {
         // The entire body of this function comes from get_swing_strike_threshold in resim's
         // formulas.py
+        let f = &self.formulas(game).swing_in_zone;
+        let mut t = ThresholdTrace::default();
         let batter = game.batter();
-        let divinity = batter.attribute(Attribute::Divinity).multiplied();
-        let muscularity = batter.attribute(Attribute::Musclitude).multiplied();
-        let inverse_patheticism = batter
-            .attribute(Attribute::Patheticism)
-            .inverse_multiplied();
-        let thwackability = batter.attribute(Attribute::Thwackability).multiplied();
+        let divinity = t.attribute("batter", &batter, Attribute::Divinity, Scaling::Multiplied);
+        let muscularity = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Musclitude,
+            Scaling::Multiplied,
+        );
+        let inverse_patheticism = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Patheticism,
+            Scaling::InverseMultiplied,
+        );
+        let thwackability = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Thwackability,
+            Scaling::Multiplied,
+        );
         let combined_batting = (divinity + muscularity + inverse_patheticism + thwackability) / 4.;
 
-        let ruthlessness = game
-            .pitcher()
-            .attribute(Attribute::Ruthlessness)
-            .multiplied();
-
-        let viscosity = 0.5; // Ballpark attributes are mostly assumed 0.5 before parks existed
-
-        0.7 + 0.35 * combined_batting - 0.4 * ruthlessness + 0.2 * (viscosity - 0.5)
+        let ruthlessness = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Ruthlessness,
+            Scaling::Multiplied,
+        );
+
+        let viscosity = t.stadium("viscosity", game.game.stadium.viscosity);
+
+        t.finish(
+            f.intercept
+                + f.combined_batting * combined_batting
+                + f.pitcher_ruthlessness * ruthlessness
+                + f.stadium_viscosity * (viscosity - 0.5),
+        )
     }
 
-    pub fn swing_on_pitch_not_in_zone(&self, game: &sim::GameAtTick) -> f64 {
+    pub fn swing_on_pitch_not_in_zone(&self, game: &sim::GameAtTick) -> Threshold {
         // The entire body of this function comes from get_swing_strike_threshold in resim's
         // formulas.py
+        let f = &self.formulas(game).swing_not_in_zone;
+        let mut t = ThresholdTrace::default();
         let batter = game.batter();
-        let moxie = batter.attribute(Attribute::Moxie).multiplied();
-        let patheticism = batter
-            .attribute(Attribute::Patheticism)
-            .inverse_multiplied();
+        let moxie = t.attribute("batter", &batter, Attribute::Moxie, Scaling::Multiplied);
+        let patheticism = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Patheticism,
+            Scaling::InverseMultiplied,
+        );
+
+        let ruthlessness = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Ruthlessness,
+            Scaling::Multiplied,
+        );
+
+        let viscosity = t.stadium("viscosity", game.game.stadium.viscosity);
+
+        let combined = (f.pitcher_ruthlessness * ruthlessness
+            + f.batter_moxie * moxie
+            + f.batter_patheticism * patheticism
+            + f.stadium_viscosity * viscosity)
+            / f.divisor;
+        let threshold = t.clamp(combined.powf(f.exponent), Some(f.min), Some(f.max));
+        t.finish(threshold)
+    }
 
-        let ruthlessness = game
-            .pitcher()
-            .attribute(Attribute::Ruthlessness)
-            .multiplied();
+    pub fn contact(&self, in_zone: bool, game: &sim::GameAtTick) -> Threshold {
+        if in_zone {
+            self.contact_on_pitch_in_zone(game)
+        } else {
+            self.contact_on_pitch_not_in_zone(game)
+        }
+    }
 
-        let viscosity = 0.5; // Ballpark attributes are mostly assumed 0.5 before parks existed
+    // Shared by both contact formulas, which weight the stadium the same way
+    fn contact_ballpark_sum(
+        t: &mut ThresholdTrace,
+        stadium: &sim::Stadium,
+        fortification: f64,
+        viscosity: f64,
+        forwardness: f64,
+        divisor: f64,
+    ) -> f64 {
+        (fortification * (t.stadium("fortification", stadium.fortification) - 0.5)
+            + viscosity * (t.stadium("viscosity", stadium.viscosity) - 0.5)
+            + forwardness * (t.stadium("forwardness", stadium.forwardness) - 0.5))
+            / divisor
+    }
 
-        let combined = (12. * ruthlessness - 5. * moxie + 5. * patheticism + 4. * viscosity) / 20.;
-        combined.powf(1.5).min(0.95).max(0.1)
+    pub fn contact_on_pitch_in_zone(&self, game: &sim::GameAtTick) -> Threshold {
+        // From get_contact_strike_threshold in resim's formulas.py
+        let f = &self.formulas(game).contact_in_zone;
+        let mut t = ThresholdTrace::default();
+        let batter = game.batter();
+        let divinity = t.attribute("batter", &batter, Attribute::Divinity, Scaling::Multiplied);
+        let musclitude = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Musclitude,
+            Scaling::Multiplied,
+        );
+        let thwackability = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Thwackability,
+            Scaling::Multiplied,
+        );
+        let inverse_patheticism = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Patheticism,
+            Scaling::InverseMultipliedComplement,
+        );
+        let combined_batting = (divinity + musclitude + thwackability + inverse_patheticism) / 4.;
+
+        let ruthlessness = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Ruthlessness,
+            Scaling::Multiplied,
+        );
+
+        let ballpark_sum = Self::contact_ballpark_sum(
+            &mut t,
+            &game.game.stadium,
+            f.stadium_fortification,
+            f.stadium_viscosity,
+            f.stadium_forwardness,
+            f.ballpark_divisor,
+        );
+
+        let threshold = f.intercept
+            + f.pitcher_ruthlessness * ruthlessness
+            + f.ballpark * ballpark_sum
+            + f.combined_batting * combined_batting.powf(f.combined_batting_exponent);
+        let threshold = t.clamp(threshold, None, Some(f.max));
+        t.finish(threshold)
     }
 
-    pub fn fair_ball(&self, game: &sim::GameAtTick) -> f64 {
-        let batter_vibes = game.batter().vibes;
-        let forwardness = 0.5;
-        let obtuseness = 0.5;
+    pub fn contact_on_pitch_not_in_zone(&self, game: &sim::GameAtTick) -> Threshold {
+        // From get_contact_ball_threshold in resim's formulas.py
+        let f = &self.formulas(game).contact_not_in_zone;
+        let mut t = ThresholdTrace::default();
+        let inverse_patheticism = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Patheticism,
+            Scaling::InverseMultipliedComplement,
+        );
+
+        let ruthlessness = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Ruthlessness,
+            Scaling::Multiplied,
+        );
+
+        let ballpark_sum = Self::contact_ballpark_sum(
+            &mut t,
+            &game.game.stadium,
+            f.stadium_fortification,
+            f.stadium_viscosity,
+            f.stadium_forwardness,
+            f.ballpark_divisor,
+        );
+
+        let threshold = f.intercept
+            + f.pitcher_ruthlessness * ruthlessness
+            + f.batter_patheticism * inverse_patheticism.powf(f.batter_patheticism_exponent)
+            + f.ballpark * ballpark_sum;
+        let threshold = t.clamp(threshold, None, Some(f.max));
+        t.finish(threshold)
+    }
+
+    pub fn fair_ball(&self, game: &sim::GameAtTick) -> Threshold {
+        let f = &self.formulas(game).fair_ball;
+        let mut t = ThresholdTrace::default();
+        let batter = game.batter();
+        let forwardness = t.stadium("forwardness", game.game.stadium.forwardness);
+        let obtuseness = t.stadium("obtuseness", game.game.stadium.obtuseness);
 
-        let batter_musclitude = game
-            .batter()
-            .attribute(Attribute::Musclitude)
-            .multiplied()
-            .vibed(batter_vibes);
+        let batter_musclitude =
+            t.attribute("batter", &batter, Attribute::Musclitude, Scaling::Vibed);
 
-        let batter_thwackability = game
-            .batter()
-            .attribute(Attribute::Thwackability)
-            .multiplied()
-            .vibed(batter_vibes);
+        let batter_thwackability =
+            t.attribute("batter", &batter, Attribute::Thwackability, Scaling::Vibed);
 
-        let batter_divinity = game
-            .batter()
-            .attribute(Attribute::Divinity)
-            .multiplied()
-            .vibed(batter_vibes);
+        let batter_divinity = t.attribute("batter", &batter, Attribute::Divinity, Scaling::Vibed);
 
         let batter_sum = (batter_musclitude + batter_thwackability + batter_divinity) / 3.0;
 
-        0.25 + 0.1 * forwardness - 0.1 * obtuseness + 0.1 * batter_sum
+        t.finish(
+            f.intercept
+                + f.stadium_forwardness * forwardness
+                + f.stadium_obtuseness * obtuseness
+                + f.combined_batting * batter_sum,
+        )
     }
 
-    pub fn party(&self) -> f64 {
-        0.0055
+    pub fn weather(&self, weather: Weather) -> Option<Threshold> {
+        self.weather
+            .get(&weather)
+            .map(|&chance| ThresholdTrace::default().finish(chance))
     }
 
-    pub fn mild_pitch(&self) -> f64 {
+    pub fn party(&self, game: &sim::GameAtTick) -> Threshold {
+        ThresholdTrace::default().finish(self.formulas(game).party)
+    }
+
+    pub fn mild_pitch(&self, game: &sim::GameAtTick) -> Threshold {
+        let f = &self.formulas(game).mild_pitch;
+        let mut t = ThresholdTrace::default();
         // Mysticism was always treated as 0.5 in s12
-        let mysticism = 0.5;
-        0.0005 + 0.004 * mysticism
-    }
-
-    pub fn out(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> f64 {
-        let batter_vibes = game.batter().vibes;
-        let pitcher_vibes = game.pitcher().vibes;
-        let fielder_vibes = fielder.vibes;
-
-        let batter_thwackability = game
-            .batter()
-            .attribute(Attribute::Thwackability)
-            .multiplied()
-            .vibed(batter_vibes);
-
-        let pitcher_unthwackability = game
-            .pitcher()
-            .attribute(Attribute::Unthwackability)
-            .multiplied()
-            .vibed(pitcher_vibes);
-
-        let fielder_omniscience = fielder
-            .attribute(Attribute::Omniscience)
-            .multiplied()
-            .vibed(fielder_vibes);
-
-        let stadium_grandiosity = 0.5;
-        let stadium_ominousness = 0.5;
-        let stadium_inconvenience = 0.5;
-        let stadium_viscosity = 0.5;
-        let stadium_forwardness = 0.5;
-
-        0.315 + 0.1 * batter_thwackability
-            - 0.08 * pitcher_unthwackability
-            - 0.07 * fielder_omniscience
-            + 0.0145 * (stadium_grandiosity - 0.5)
-            + 0.0085 * (stadium_ominousness - 0.5)
-            - 0.011 * (stadium_inconvenience - 0.5)
-            - 0.005 * (stadium_viscosity - 0.5)
-            + 0.01 * (stadium_forwardness - 0.5)
-    }
-
-    pub fn fly(&self, game: &sim::GameAtTick) -> f64 {
-        // No vibes, and inverse multiplied for some reason
-        let batter_buoyancy = game
-            .batter()
-            .attribute(Attribute::Buoyancy)
-            .inverse_multiplied();
+        let mysticism = t.constant("pitcher mysticism", 0.5);
+        t.finish(f.intercept + f.mysticism * mysticism)
+    }
 
-        // Note for implementation: this needs to use the *pitcher*'s team.
+    pub fn out(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> Threshold {
+        let f = &self.formulas(game).out;
+        let mut t = ThresholdTrace::default();
+
+        let batter_thwackability = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Thwackability,
+            Scaling::Vibed,
+        );
+
+        let pitcher_unthwackability = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Unthwackability,
+            Scaling::Vibed,
+        );
+
+        let fielder_omniscience =
+            t.attribute("fielder", fielder, Attribute::Omniscience, Scaling::Vibed);
+
+        let stadium = &game.game.stadium;
+        let stadium_grandiosity = t.stadium("grandiosity", stadium.grandiosity);
+        let stadium_ominousness = t.stadium("ominousness", stadium.ominousness);
+        let stadium_inconvenience = t.stadium("inconvenience", stadium.inconvenience);
+        let stadium_viscosity = t.stadium("viscosity", stadium.viscosity);
+        let stadium_forwardness = t.stadium("forwardness", stadium.forwardness);
+
+        t.finish(
+            f.intercept
+                + f.batter_thwackability * batter_thwackability
+                + f.pitcher_unthwackability * pitcher_unthwackability
+                + f.fielder_omniscience * fielder_omniscience
+                + f.stadium_grandiosity * (stadium_grandiosity - 0.5)
+                + f.stadium_ominousness * (stadium_ominousness - 0.5)
+                + f.stadium_inconvenience * (stadium_inconvenience - 0.5)
+                + f.stadium_viscosity * (stadium_viscosity - 0.5)
+                + f.stadium_forwardness * (stadium_forwardness - 0.5),
+        )
+    }
+
+    pub fn fly(&self, game: &sim::GameAtTick) -> Threshold {
+        let f = &self.formulas(game).fly;
+        let mut t = ThresholdTrace::default();
+        // No vibes, and inverse multiplied for some reason
+        let batter_buoyancy = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Buoyancy,
+            Scaling::InverseMultiplied,
+        );
+
+        // This is the batter's suppression, but with the pitcher's mods.
         // Presumably it was supposed to use the pitcher's suppression as well
         // but TGB made a typo.
         // Also, no vibes
-        let suppression = game.batter().attribute(Attribute::Suppression).multiplied();
-
-        let stadium_ominousness = 0.5;
-
-        let threshold =
-            0.18 + 0.3 * batter_buoyancy - 0.16 * suppression - 0.1 * (stadium_ominousness - 0.5);
-        threshold.max(0.01)
-    }
-
-    pub fn hr(&self, game: &sim::GameAtTick) -> f64 {
-        let batter_vibes = game.batter().vibes;
-        let pitcher_vibes = game.pitcher().vibes;
-
-        let batter_divinity = game
-            .batter()
-            .attribute(Attribute::Divinity)
-            .multiplied()
-            .vibed(batter_vibes);
-
-        let pitcher_overpowerment = game
-            .pitcher()
-            .attribute(Attribute::Overpowerment)
-            .multiplied()
-            .vibed(pitcher_vibes);
-
-        let pitcher_suppression = game
-            .pitcher()
-            .attribute(Attribute::Suppression)
-            .multiplied()
-            .vibed(pitcher_vibes);
-
-        let stadium_grandiosity = 0.5;
-        let stadium_fortification = 0.5;
-        let stadium_viscosity = 0.5;
-        let stadium_ominousness = 0.5;
-        let stadium_forwardness = 0.5;
-        let ballpark_sum = 0.4 * (stadium_grandiosity - 0.5)
-            + 0.2 * (stadium_fortification - 0.5)
-            + 0.08 * (stadium_viscosity - 0.5)
-            + 0.08 * (stadium_ominousness - 0.5)
-            - 0.24 * (stadium_forwardness - 0.5);
-
-        let opw_supp = (10.0 * pitcher_overpowerment + pitcher_suppression) / 11.0;
-        0.12 + 0.16 * batter_divinity - 0.08 * opw_supp - 0.18 * ballpark_sum
-    }
-
-    pub fn double(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> f64 {
-        let batter_vibes = game.batter().vibes;
-        let pitcher_vibes = game.pitcher().vibes;
-        let fielder_vibes = fielder.vibes;
-
-        let batter_musclitude = game
-            .batter()
-            .attribute(Attribute::Musclitude)
-            .multiplied()
-            .vibed(batter_vibes);
-
-        let pitcher_overpowerment = game
-            .pitcher()
-            .attribute(Attribute::Overpowerment)
-            .multiplied()
-            .vibed(pitcher_vibes);
-
-        let fielder_chasiness = fielder
-            .attribute(Attribute::Chasiness)
-            .multiplied()
-            .vibed(fielder_vibes);
-
-        let stadium_forwardness = 0.5;
-        let stadium_elongation = 0.5;
-        let stadium_viscosity = 0.5;
-        let stadium_ominousness = 0.5;
-        let ballpark_sum = 0.027 * (stadium_forwardness - 0.5) - 0.015 * (stadium_elongation - 0.5)
-            + 0.01 * (stadium_ominousness - 0.5)
-            + 0.008 * (stadium_viscosity - 0.5);
-
-        0.17 + 0.2 * batter_musclitude - 0.04 * pitcher_overpowerment - 0.1 * fielder_chasiness
-            + ballpark_sum
-    }
-
-    pub fn triple(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> f64 {
-        let batter_vibes = game.batter().vibes;
-        let pitcher_vibes = game.pitcher().vibes;
-        let fielder_vibes = fielder.vibes;
-
-        let batter_ground_friction = game
-            .batter()
-            .attribute(Attribute::GroundFriction)
-            .multiplied()
-            .vibed(batter_vibes);
-
-        let pitcher_overpowerment = game
-            .pitcher()
-            .attribute(Attribute::Overpowerment)
-            .multiplied()
-            .vibed(pitcher_vibes);
-
-        let fielder_chasiness = fielder
-            .attribute(Attribute::Chasiness)
-            .multiplied()
-            .vibed(fielder_vibes);
-
-        let stadium_forwardness = 0.5;
-        let stadium_grandiosity = 0.5;
-        let stadium_obtuseness = 0.5;
-        let stadium_viscosity = 0.5;
-        let stadium_ominousness = 0.5;
-        let ballpark_sum = (3.0 * (stadium_forwardness - 0.5) - 5.0 * (stadium_grandiosity - 0.5)
-            + 5.0 * (stadium_obtuseness - 0.5)
-            + 1.0 * (stadium_viscosity - 0.5)
-            + 1.0 * (stadium_ominousness - 0.5))
-            / 15.0;
-
-        0.05 + 0.2 * batter_ground_friction
-            - 0.04 * pitcher_overpowerment
-            - 0.06 * fielder_chasiness
-            + 0.1 * ballpark_sum
-    }
-
-    pub fn advance_on_hit(&self, runner: &PlayerAtTick, fielder: &PlayerAtTick) -> f64 {
+        let suppression = t.attribute(
+            "batter",
+            &game.batter_with_pitcher_multipliers(),
+            Attribute::Suppression,
+            Scaling::Multiplied,
+        );
+
+        let stadium_ominousness = t.stadium("ominousness", game.game.stadium.ominousness);
+
+        let threshold = f.intercept
+            + f.batter_buoyancy * batter_buoyancy
+            + f.suppression * suppression
+            + f.stadium_ominousness * (stadium_ominousness - 0.5);
+        let threshold = t.clamp(threshold, Some(f.min), None);
+        t.finish(threshold)
+    }
+
+    pub fn hr(&self, game: &sim::GameAtTick) -> Threshold {
+        let f = &self.formulas(game).hr;
+        let mut t = ThresholdTrace::default();
+        let pitcher = game.pitcher();
+
+        let batter_divinity = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Divinity,
+            Scaling::Vibed,
+        );
+
+        let pitcher_overpowerment = t.attribute(
+            "pitcher",
+            &pitcher,
+            Attribute::Overpowerment,
+            Scaling::Vibed,
+        );
+
+        let pitcher_suppression =
+            t.attribute("pitcher", &pitcher, Attribute::Suppression, Scaling::Vibed);
+
+        let stadium = &game.game.stadium;
+        let stadium_grandiosity = t.stadium("grandiosity", stadium.grandiosity);
+        let stadium_fortification = t.stadium("fortification", stadium.fortification);
+        let stadium_viscosity = t.stadium("viscosity", stadium.viscosity);
+        let stadium_ominousness = t.stadium("ominousness", stadium.ominousness);
+        let stadium_forwardness = t.stadium("forwardness", stadium.forwardness);
+        let ballpark_sum = f.stadium_grandiosity * (stadium_grandiosity - 0.5)
+            + f.stadium_fortification * (stadium_fortification - 0.5)
+            + f.stadium_viscosity * (stadium_viscosity - 0.5)
+            + f.stadium_ominousness * (stadium_ominousness - 0.5)
+            + f.stadium_forwardness * (stadium_forwardness - 0.5);
+
+        let opw_supp = (f.pitcher_overpowerment * pitcher_overpowerment
+            + f.pitcher_suppression * pitcher_suppression)
+            / f.pitcher_divisor;
+        t.finish(
+            f.intercept
+                + f.batter_divinity * batter_divinity
+                + f.pitcher * opw_supp
+                + f.ballpark * ballpark_sum,
+        )
+    }
+
+    pub fn double(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> Threshold {
+        let f = &self.formulas(game).double;
+        let mut t = ThresholdTrace::default();
+
+        let batter_musclitude = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Musclitude,
+            Scaling::Vibed,
+        );
+
+        let pitcher_overpowerment = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Overpowerment,
+            Scaling::Vibed,
+        );
+
+        let fielder_chasiness =
+            t.attribute("fielder", fielder, Attribute::Chasiness, Scaling::Vibed);
+
+        let stadium = &game.game.stadium;
+        let stadium_forwardness = t.stadium("forwardness", stadium.forwardness);
+        let stadium_elongation = t.stadium("elongation", stadium.elongation);
+        let stadium_viscosity = t.stadium("viscosity", stadium.viscosity);
+        let stadium_ominousness = t.stadium("ominousness", stadium.ominousness);
+        let ballpark_sum = f.stadium_forwardness * (stadium_forwardness - 0.5)
+            + f.stadium_elongation * (stadium_elongation - 0.5)
+            + f.stadium_ominousness * (stadium_ominousness - 0.5)
+            + f.stadium_viscosity * (stadium_viscosity - 0.5);
+
+        t.finish(
+            f.intercept
+                + f.batter_musclitude * batter_musclitude
+                + f.pitcher_overpowerment * pitcher_overpowerment
+                + f.fielder_chasiness * fielder_chasiness
+                + ballpark_sum,
+        )
+    }
+
+    pub fn triple(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> Threshold {
+        let f = &self.formulas(game).triple;
+        let mut t = ThresholdTrace::default();
+
+        let batter_ground_friction = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::GroundFriction,
+            Scaling::Vibed,
+        );
+
+        let pitcher_overpowerment = t.attribute(
+            "pitcher",
+            &game.pitcher(),
+            Attribute::Overpowerment,
+            Scaling::Vibed,
+        );
+
+        let fielder_chasiness =
+            t.attribute("fielder", fielder, Attribute::Chasiness, Scaling::Vibed);
+
+        let stadium = &game.game.stadium;
+        let stadium_forwardness = t.stadium("forwardness", stadium.forwardness);
+        let stadium_grandiosity = t.stadium("grandiosity", stadium.grandiosity);
+        let stadium_obtuseness = t.stadium("obtuseness", stadium.obtuseness);
+        let stadium_viscosity = t.stadium("viscosity", stadium.viscosity);
+        let stadium_ominousness = t.stadium("ominousness", stadium.ominousness);
+        let ballpark_sum = (f.stadium_forwardness * (stadium_forwardness - 0.5)
+            + f.stadium_grandiosity * (stadium_grandiosity - 0.5)
+            + f.stadium_obtuseness * (stadium_obtuseness - 0.5)
+            + f.stadium_viscosity * (stadium_viscosity - 0.5)
+            + f.stadium_ominousness * (stadium_ominousness - 0.5))
+            / f.ballpark_divisor;
+
+        t.finish(
+            f.intercept
+                + f.batter_ground_friction * batter_ground_friction
+                + f.pitcher_overpowerment * pitcher_overpowerment
+                + f.fielder_chasiness * fielder_chasiness
+                + f.ballpark * ballpark_sum,
+        )
+    }
+
+    pub fn advance_on_hit(
+        &self,
+        game: &sim::GameAtTick,
+        runner: &PlayerAtTick,
+        fielder: &PlayerAtTick,
+    ) -> Threshold {
+        Self::advance(&self.formulas(game).advance_on_hit, runner, fielder)
+    }
+
+    // `fielder` is the one who caught the ball for flyouts and the one who
+    // made the out for ground outs
+    pub fn advance_on_out(
+        &self,
+        game: &sim::GameAtTick,
+        runner: &PlayerAtTick,
+        fielder: &PlayerAtTick,
+        is_flyout: bool,
+    ) -> Option<Threshold> {
+        let formulas = self.formulas(game);
+        let f = if is_flyout {
+            &formulas.tag_up
+        } else {
+            &formulas.advance_on_ground_out
+        };
+        Some(Self::advance(f.as_ref()?, runner, fielder))
+    }
+
+    fn advance(f: &AdvanceFormula, runner: &PlayerAtTick, fielder: &PlayerAtTick) -> Threshold {
+        let mut t = ThresholdTrace::default();
         // Not vibed
-        let fielder_tenaciousness = fielder.attribute(Attribute::Tenaciousness).multiplied();
+        let fielder_tenaciousness = t.attribute(
+            "fielder",
+            fielder,
+            Attribute::Tenaciousness,
+            Scaling::Multiplied,
+        );
 
         // Not vibed or multiplied
-        let runner_continuation = runner.attribute(Attribute::Continuation).base_value;
+        let runner_continuation =
+            t.attribute("runner", runner, Attribute::Continuation, Scaling::Base);
+
+        let threshold = f.intercept
+            + f.fielder_tenaciousness * fielder_tenaciousness
+            + f.runner_continuation * runner_continuation;
+        let threshold = t.clamp(threshold, Some(f.min), Some(f.max));
+        t.finish(threshold)
+    }
+
+    // `fielder` is the one who made the out, not the one who caught the ball
+    pub fn double_play(&self, game: &sim::GameAtTick, fielder: &PlayerAtTick) -> Option<Threshold> {
+        let f = self.formulas(game).double_play.as_ref()?;
+        let mut t = ThresholdTrace::default();
+        let batter_laserlikeness = t.attribute(
+            "batter",
+            &game.batter(),
+            Attribute::Laserlikeness,
+            Scaling::Vibed,
+        );
+        let fielder_tenaciousness =
+            t.attribute("fielder", fielder, Attribute::Tenaciousness, Scaling::Vibed);
+
+        let threshold = f.intercept
+            + f.batter_laserlikeness * batter_laserlikeness
+            + f.fielder_tenaciousness * fielder_tenaciousness;
+        let threshold = t.clamp(threshold, Some(f.min), Some(f.max));
+        Some(t.finish(threshold))
+    }
+
+    pub fn steal_attempt(
+        &self,
+        game: &sim::GameAtTick,
+        runner: &PlayerAtTick,
+        fielder: &PlayerAtTick,
+    ) -> Option<Threshold> {
+        let f = self.formulas(game).steal_attempt.as_ref()?;
+        Some(Self::steal(f, runner, fielder))
+    }
+
+    pub fn steal_success(
+        &self,
+        game: &sim::GameAtTick,
+        runner: &PlayerAtTick,
+        fielder: &PlayerAtTick,
+    ) -> Option<Threshold> {
+        let f = self.formulas(game).steal_success.as_ref()?;
+        Some(Self::steal(f, runner, fielder))
+    }
+
+    fn steal(f: &StealFormula, runner: &PlayerAtTick, fielder: &PlayerAtTick) -> Threshold {
+        let mut t = ThresholdTrace::default();
+        let runner_laserlikeness =
+            t.attribute("runner", runner, Attribute::Laserlikeness, Scaling::Vibed);
+        let fielder_watchfulness =
+            t.attribute("fielder", fielder, Attribute::Watchfulness, Scaling::Vibed);
+
+        let threshold = f.intercept
+            + f.runner_laserlikeness * runner_laserlikeness
+            + f.fielder_watchfulness * fielder_watchfulness;
+        let threshold = t.clamp(threshold, Some(f.min), Some(f.max));
+        t.finish(threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_thresholds_load() {
+        let thresholds = Thresholds::load().expect("Bundled thresholds should load");
+        assert_eq!(thresholds.formulas[0].first_season, 1);
+        assert_eq!(thresholds.formulas.last().unwrap().last_season, None);
+    }
+
+    // A boosted batter is less pathetic, so their inverse patheticism goes up
+    #[test]
+    fn inverse_patheticism_divides_then_complements() {
+        let player = sim::Player::average(1, "Batter".to_string());
+        let batter = PlayerAtTick::with_multiplier(&player, 1.25);
+        let mut t = ThresholdTrace::default();
+
+        let value = t.attribute(
+            "batter",
+            &batter,
+            Attribute::Patheticism,
+            Scaling::InverseMultipliedComplement,
+        );
+
+        // 1 - 0.5 / 1.25
+        assert!((value - 0.6).abs() < 1e-12, "{value}");
+    }
 
-        (0.7 - fielder_tenaciousness + 0.6 * runner_continuation)
-            .min(0.95)
-            .max(0.01)
+    #[test]
+    fn discipline_and_s12_have_their_own_sets() {
+        let thresholds = Thresholds::load().expect("Bundled thresholds should load");
+        assert_eq!(thresholds.formulas_for_season(1).first_season, 1);
+        assert_eq!(thresholds.formulas_for_season(11).first_season, 1);
+        assert_eq!(thresholds.formulas_for_season(12).first_season, 12);
+        assert_eq!(thresholds.formulas_for_season(24).first_season, 12);
     }
 }
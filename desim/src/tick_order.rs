@@ -0,0 +1,428 @@
+use crate::engine::{reconstruct_missing_updates, MissingUpdates, ReconstructedGap};
+use crate::fragments::RollStream;
+use crate::rng::Rng;
+use crate::rolls::{infer_outcomes, rolls_for_update, RollData};
+use crate::thresholds::Thresholds;
+use crate::{sim, update_parser};
+use blaseball_api::ChroniclerGameUpdate;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Chron gives us every update that happened during a tick, but not the order
+// the sim processed the games in. Since all games share one Rng, the order
+// matters for every roll. This module tries orderings against a copy of the
+// Rng and picks the one whose rolls agree with what we observed. When Chron
+// missed updates before one of them, each way of filling the gap is tried as
+// part of the same search, since the gap's rolls move every roll after it.
+
+// A full day has up to 12 games, and 12! orderings is a lot. The search prunes
+// any ordering that's already worse than the best one found, and a correct
+// ordering normally prunes everything else, so this limit is only a backstop
+// for ticks where nothing is consistent.
+const MAX_CANDIDATES: usize = 100_000;
+
+pub struct TickOrder {
+    // Indices into the tick's updates, in the order they should be run
+    pub order: Vec<usize>,
+    // For each update, by its index in the tick, which of the
+    // reconstructions of its missing updates to run before it. Zero for
+    // updates with nothing missing.
+    pub gap_choices: Vec<usize>,
+    // How many rolls disagreed with an observation in the chosen order. Zero
+    // unless no ordering was fully consistent.
+    pub contradictions: usize,
+    // True if some other ordering, which puts the rolls in a different order,
+    // was also free of contradictions. Then the chosen one is just the first
+    // one the search found.
+    pub ambiguous: bool,
+    // True if the search hit `MAX_CANDIDATES` before trying every ordering
+    pub gave_up: bool,
+}
+
+// What running one update against the Rng looked like
+struct UpdateScore {
+    contradictions: usize,
+    // Updates that don't roll can't change anything about the updates after
+    // them, so the search doesn't need to try them in every position
+    rolled: bool,
+}
+
+// One way an update can go, which is one per reconstruction of the updates
+// Chron missed before it, and where it leaves the Rng and the check rolls
+struct UpdateBranch {
+    score: UpdateScore,
+    rng: Rng,
+    check_offset: usize,
+}
+
+// The best complete ordering the search has found so far
+struct BestOrdering {
+    order: Vec<usize>,
+    gap_choices: Vec<usize>,
+    rolled: Vec<(usize, usize)>,
+    contradictions: usize,
+}
+
+struct TickOrderSearch<F> {
+    num_updates: usize,
+    score_update: F,
+
+    order: Vec<usize>,
+    gap_choices: Vec<usize>,
+    // Like `order`, but only the updates that rolled, with their gap choices
+    rolled: Vec<(usize, usize)>,
+    used: Vec<bool>,
+    best: Option<BestOrdering>,
+    ambiguous: bool,
+    candidates: usize,
+    gave_up: bool,
+}
+
+impl<F> TickOrderSearch<F>
+where
+    F: Fn(usize, &Rng, usize) -> Vec<UpdateBranch>,
+{
+    fn should_prune(&self, contradictions: usize) -> bool {
+        let Some(best) = &self.best else {
+            return false;
+        };
+
+        // Ties with a perfect ordering are still worth following until we
+        // know whether they're a different perfect ordering
+        contradictions > best.contradictions
+            || (contradictions == best.contradictions
+                && (best.contradictions > 0 || self.ambiguous))
+    }
+
+    fn finish_ordering(&mut self, contradictions: usize) {
+        match &self.best {
+            Some(best) if contradictions == best.contradictions => {
+                if best.rolled != self.rolled {
+                    self.ambiguous = true;
+                }
+            }
+            _ => {
+                self.best = Some(BestOrdering {
+                    order: self.order.clone(),
+                    gap_choices: self.gap_choices.clone(),
+                    rolled: self.rolled.clone(),
+                    contradictions,
+                });
+                self.ambiguous = false;
+            }
+        }
+    }
+
+    fn visit(&mut self, rng: &Rng, check_offset: usize, contradictions: usize) {
+        if self.should_prune(contradictions) {
+            return;
+        }
+
+        if self.order.len() == self.num_updates {
+            self.finish_ordering(contradictions);
+            return;
+        }
+
+        for update_index in 0..self.num_updates {
+            if self.used[update_index] {
+                continue;
+            }
+
+            let mut rolled = false;
+            for (gap_choice, branch) in (self.score_update)(update_index, rng, check_offset)
+                .into_iter()
+                .enumerate()
+            {
+                if self.candidates >= MAX_CANDIDATES {
+                    self.gave_up = true;
+                    return;
+                }
+                self.candidates += 1;
+
+                self.used[update_index] = true;
+                self.order.push(update_index);
+                self.gap_choices[update_index] = gap_choice;
+                if branch.score.rolled {
+                    self.rolled.push((update_index, gap_choice));
+                }
+                self.visit(
+                    &branch.rng,
+                    branch.check_offset,
+                    contradictions + branch.score.contradictions,
+                );
+                if branch.score.rolled {
+                    self.rolled.pop();
+                }
+                self.gap_choices[update_index] = 0;
+                self.order.pop();
+                self.used[update_index] = false;
+
+                rolled |= branch.score.rolled;
+            }
+
+            // Any ordering that puts one of the remaining updates here and
+            // this one later has the same rolls as one that puts this one
+            // here, which we just tried
+            if !rolled {
+                return;
+            }
+        }
+    }
+}
+
+fn search_orderings<F>(num_updates: usize, rng: &Rng, score_update: F) -> TickOrder
+where
+    F: Fn(usize, &Rng, usize) -> Vec<UpdateBranch>,
+{
+    let mut search = TickOrderSearch {
+        num_updates,
+        score_update,
+        order: Vec::with_capacity(num_updates),
+        gap_choices: vec![0; num_updates],
+        rolled: Vec::with_capacity(num_updates),
+        used: vec![false; num_updates],
+        best: None,
+        ambiguous: false,
+        candidates: 0,
+        gave_up: false,
+    };
+
+    search.visit(rng, 0, 0);
+
+    // The first ordering the search completes is Chron's order, so `best` can
+    // only be None if the budget ran out before then
+    let best = search.best.unwrap_or_else(|| BestOrdering {
+        order: (0..num_updates).collect(),
+        gap_choices: vec![0; num_updates],
+        rolled: Vec::new(),
+        contradictions: 0,
+    });
+
+    TickOrder {
+        order: best.order,
+        gap_choices: best.gap_choices,
+        contradictions: best.contradictions,
+        ambiguous: search.ambiguous,
+        gave_up: search.gave_up,
+    }
+}
+
+// Counts how many of an update's rolls disagreed with observations, moving
+// `check_offset` past them
+fn count_contradictions(
+    rolls: &mut [RollData],
+    check_rolls: Option<&RollStream>,
+    check_offset: &mut usize,
+) -> usize {
+    infer_outcomes(rolls).len()
+        + rolls
+            .iter()
+            .filter(|roll| {
+                let check_roll = check_rolls.and_then(|check_rolls| check_rolls.get(*check_offset));
+                *check_offset += 1;
+
+                roll.agrees_with_outcome() == Some(false)
+                    || check_roll.is_some_and(|check_roll| check_roll.roll != roll.roll)
+            })
+            .count()
+}
+
+// Generates the rolls for one update, after the rolls for any updates Chron
+// missed before it, and counts how many of them disagreed with observations.
+// There's a branch for each way the missing updates could have gone.
+fn score_update(
+    game: &sim::Game,
+    update: &ChroniclerGameUpdate,
+    th: &Thresholds,
+    check_rolls: Option<&RollStream>,
+    rng: &Rng,
+    check_offset: usize,
+) -> Vec<UpdateBranch> {
+    // Unparsed updates produce no rolls, so they can go anywhere
+    let Ok(parsed_update) = update_parser::parse_update(update) else {
+        return vec![UpdateBranch {
+            score: UpdateScore {
+                contradictions: 0,
+                rolled: false,
+            },
+            rng: rng.clone(),
+            check_offset,
+        }];
+    };
+
+    let gaps = match reconstruct_missing_updates(game, update, th, rng) {
+        MissingUpdates::Reconstructed(reconstructed) => reconstructed,
+        MissingUpdates::None | MissingUpdates::Unreconstructed { .. } => vec![ReconstructedGap {
+            missing: Vec::new(),
+            rolls: Vec::new(),
+            rng: rng.clone(),
+        }],
+    };
+
+    let game_at_tick = game.at_tick(update);
+    gaps.into_iter()
+        .map(|gap| {
+            let mut rng = gap.rng;
+            let mut check_offset = check_offset;
+            let mut contradictions = 0;
+            let mut rolled = false;
+            for mut rolls in gap.rolls {
+                contradictions += count_contradictions(&mut rolls, check_rolls, &mut check_offset);
+                rolled |= !rolls.is_empty();
+            }
+
+            let parsed_update = parsed_update.clone();
+            let score = match rolls_for_update(&mut rng, parsed_update, th, &game_at_tick) {
+                Ok(mut rolls) => UpdateScore {
+                    contradictions: contradictions
+                        + count_contradictions(&mut rolls, check_rolls, &mut check_offset),
+                    rolled: rolled || !rolls.is_empty(),
+                },
+                // An update this ordering can't produce rolls for counts
+                // against it
+                Err(_) => UpdateScore {
+                    contradictions: contradictions + 1,
+                    rolled: true,
+                },
+            };
+            UpdateBranch {
+                score,
+                rng,
+                check_offset,
+            }
+        })
+        .collect()
+}
+
+pub fn solve_tick_order(
+    games: &HashMap<Uuid, sim::Game>,
+    updates: &[ChroniclerGameUpdate],
+    th: &Thresholds,
+    rng: &Rng,
+    check_rolls: Option<&RollStream>,
+) -> TickOrder {
+    // Nothing to solve. A single update still goes through the search, which
+    // picks what to fill any gap before it with.
+    if updates.is_empty() {
+        return TickOrder {
+            order: Vec::new(),
+            gap_choices: Vec::new(),
+            contradictions: 0,
+            ambiguous: false,
+            gave_up: false,
+        };
+    }
+
+    search_orderings(updates.len(), rng, |update_index, rng, check_offset| {
+        let update = &updates[update_index];
+        let game = games
+            .get(&update.game_id)
+            .expect("Every game in the tick must be fetched before solving tick order");
+        score_update(game, update, th, check_rolls, rng, check_offset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rng() -> Rng {
+        Rng::new((11489856334623440466, 7665746933450455135), 0)
+    }
+
+    // Each update is either roll-free (None) or rolls once and only agrees
+    // with observations if it's the nth roll of the tick (Some(Some(n))), or
+    // at any position (Some(None)). `check_offset` stands in for the position.
+    // Every update has one of those for each way of filling a gap before it.
+    fn solve_with_gaps(updates: &[Vec<Option<Option<usize>>>]) -> TickOrder {
+        search_orderings(updates.len(), &rng(), |update_index, rng, position| {
+            updates[update_index]
+                .iter()
+                .map(|branch| {
+                    let Some(expected) = branch else {
+                        return UpdateBranch {
+                            score: UpdateScore {
+                                contradictions: 0,
+                                rolled: false,
+                            },
+                            rng: rng.clone(),
+                            check_offset: position,
+                        };
+                    };
+
+                    let mut rng = rng.clone();
+                    rng.step(1);
+                    let agrees = expected.map_or(true, |expected| expected == position);
+                    UpdateBranch {
+                        score: UpdateScore {
+                            contradictions: usize::from(!agrees),
+                            rolled: true,
+                        },
+                        rng,
+                        check_offset: position + 1,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn solve(updates: &[Option<Option<usize>>]) -> TickOrder {
+        let updates = updates
+            .iter()
+            .map(|&update| vec![update])
+            .collect::<Vec<_>>();
+        solve_with_gaps(&updates)
+    }
+
+    #[test]
+    fn finds_the_only_consistent_order() {
+        let tick_order = solve(&[Some(Some(2)), Some(Some(0)), Some(Some(1))]);
+        assert_eq!(tick_order.order, vec![1, 2, 0]);
+        assert_eq!(tick_order.contradictions, 0);
+        assert!(!tick_order.ambiguous);
+        assert!(!tick_order.gave_up);
+    }
+
+    #[test]
+    fn reports_two_consistent_orders() {
+        let tick_order = solve(&[Some(None), Some(None), Some(Some(2))]);
+        assert_eq!(tick_order.contradictions, 0);
+        assert_eq!(tick_order.order[2], 2);
+        assert!(tick_order.ambiguous);
+    }
+
+    #[test]
+    fn updates_without_rolls_dont_make_it_ambiguous() {
+        let tick_order = solve(&[None, Some(Some(1)), None, Some(Some(0))]);
+        assert_eq!(tick_order.contradictions, 0);
+        assert!(!tick_order.ambiguous);
+
+        let rolling_order = tick_order
+            .order
+            .into_iter()
+            .filter(|&update_index| update_index % 2 == 1)
+            .collect::<Vec<_>>();
+        assert_eq!(rolling_order, vec![3, 1]);
+    }
+
+    #[test]
+    fn settles_for_the_fewest_contradictions() {
+        // Both updates want to be first
+        let tick_order = solve(&[Some(Some(0)), Some(Some(0)), Some(Some(2))]);
+        assert_eq!(tick_order.contradictions, 1);
+        assert_eq!(tick_order.order[2], 2);
+        assert!(!tick_order.ambiguous);
+    }
+
+    #[test]
+    fn picks_the_gap_that_fits() {
+        // Only the second way of filling the first update's gap puts its
+        // roll first
+        let tick_order =
+            solve_with_gaps(&[vec![Some(Some(1)), Some(Some(0))], vec![Some(Some(1))]]);
+        assert_eq!(tick_order.order, vec![0, 1]);
+        assert_eq!(tick_order.gap_choices, vec![1, 0]);
+        assert_eq!(tick_order.contradictions, 0);
+        assert!(!tick_order.ambiguous);
+    }
+}
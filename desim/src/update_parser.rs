@@ -1,36 +1,170 @@
 use crate::nom_helpers::{parse_terminated, parse_whole_number, ParserResult};
+use crate::sim::Attribute;
 use blaseball_api::ChroniclerGameUpdate;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
+use nom::bytes::complete::{tag, take_till1};
 use nom::character::complete::digit1;
-use nom::combinator::eof;
-use nom::multi::many0;
+use nom::combinator::{all_consuming, eof, opt};
+use nom::multi::{many0, separated_list1};
 use nom::Parser;
 use thiserror::Error;
 
+#[derive(Clone)]
 pub struct ParsedUpdate<'u> {
     pub data: ParsedUpdateData<'u>,
 }
 
+#[derive(Clone)]
+#[allow(dead_code)] // Not every field is used by the roll generators yet
 pub enum ParsedUpdateData<'u> {
     Empty,
+    LetsGo,
     PlayBall,
     InningTurnover,
     BatterUp,
     Ball,
     FoulBall,
     StrikeLooking,
-    StrikeoutLooking,
+    StrikeoutLooking {
+        batter: &'u str,
+    },
     StrikeSwinging,
-    StrikeoutSwinging,
-    GroundOut,
-    Flyout,
+    StrikeoutSwinging {
+        batter: &'u str,
+    },
+    GroundOut {
+        batter: &'u str,
+        fielder: &'u str,
+    },
+    Flyout {
+        batter: &'u str,
+        fielder: &'u str,
+    },
     InningEnd,
     Hit { 
+        batter: &'u str,
         bases: i64, 
         scored: Vec<&'u str>,
     },
-    DoublePlay,
+    DoublePlay {
+        batter: &'u str,
+        scored: Vec<&'u str>,
+    },
+    Walk {
+        batter: &'u str,
+        scored: Vec<&'u str>,
+    },
+    HomeRun {
+        batter: &'u str,
+        // Including the batter, so a solo home run is 1 and a grand slam is 4
+        runs: i64,
+    },
+    StolenBase {
+        runner: &'u str,
+        // The base that was stolen, using the same numbering as
+        // `bases_occupied` (so stealing second is 1)
+        base: i64,
+    },
+    CaughtStealing {
+        runner: &'u str,
+        base: i64,
+    },
+    // An out where at least one runner moved up, like a sacrifice fly or a
+    // ground out that advances a runner
+    Sacrifice {
+        batter: &'u str,
+        fielder: &'u str,
+        is_flyout: bool,
+        advanced: Vec<&'u str>,
+        scored: Vec<&'u str>,
+    },
+    FieldersChoice {
+        batter: &'u str,
+        runner_out: &'u str,
+        // The base the runner was thrown out at
+        base: i64,
+        scored: Vec<&'u str>,
+    },
+    HitByPitch {
+        batter: &'u str,
+        scored: Vec<&'u str>,
+    },
+    MildPitch {
+        is_walk: bool,
+        runners_advanced: bool,
+        scored: Vec<&'u str>,
+    },
+    PitcherChange {
+        pitcher: &'u str,
+    },
+    GameOver,
+    Party {
+        player: &'u str,
+        // Each attribute the party boosted, in the order the update lists
+        // them. Empty if the update doesn't say.
+        boosted: Vec<Attribute>,
+    },
+    // Weather events
+    Incineration {
+        // The whole description of who was incinerated, which includes their
+        // team and position
+        victim: &'u str,
+        replacement: &'u str,
+    },
+    Peanut {
+        player: &'u str,
+        allergic: bool,
+    },
+    FeedbackSwap {
+        player_a: &'u str,
+        player_b: &'u str,
+    },
+    ReverbShuffle {
+        team: &'u str,
+        shuffled: ReverbShuffled,
+    },
+    Blooddrain {
+        siphoner: &'u str,
+        victim: &'u str,
+        ability: SiphonedAbility,
+    },
+    BirdsFlavor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReverbShuffled {
+    WholeTeam,
+    SeveralPlayers,
+    Rotation,
+    Lineup,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SiphonedAbility {
+    Hitting,
+    Pitching,
+    Baserunning,
+    Defensive,
+}
+
+impl<'u> ParsedUpdateData<'u> {
+    // The batter named in the update, for updates that name one
+    pub fn batter(&self) -> Option<&'u str> {
+        match *self {
+            Self::StrikeoutLooking { batter }
+            | Self::StrikeoutSwinging { batter }
+            | Self::GroundOut { batter, .. }
+            | Self::Flyout { batter, .. }
+            | Self::Hit { batter, .. }
+            | Self::DoublePlay { batter, .. }
+            | Self::Walk { batter, .. }
+            | Self::HomeRun { batter, .. }
+            | Self::Sacrifice { batter, .. }
+            | Self::FieldersChoice { batter, .. }
+            | Self::HitByPitch { batter, .. } => Some(batter),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -40,28 +174,63 @@ pub enum UpdateParseError {
 }
 
 pub fn parse_update(game_update: &ChroniclerGameUpdate) -> Result<ParsedUpdate, UpdateParseError> {
-    let (_, data) = parse_description
-        .parse(&game_update.data.last_update)
-        .map_err(|err| UpdateParseError::FailedToParseDescription(err.to_string()))?;
+    let data = parse_whole_description(&game_update.data.last_update)?;
 
     Ok(ParsedUpdate { data })
 }
 
+// Anything left over is text the grammar doesn't know about yet, so the
+// update doesn't count as parsed
+fn parse_whole_description(input: &str) -> Result<ParsedUpdateData<'_>, UpdateParseError> {
+    let (_, data) = all_consuming(parse_description)
+        .parse(input)
+        .map_err(|err| UpdateParseError::FailedToParseDescription(err.to_string()))?;
+
+    Ok(data)
+}
+
 fn parse_description(input: &str) -> ParserResult<ParsedUpdateData> {
+    // alt only takes so many parsers at a time, so these are grouped roughly
+    // by what part of the game they come from
     alt((
-        parse_empty,
-        parse_play_ball,
-        parse_inning_turnover,
-        parse_batter_up,
-        parse_ball,
-        parse_foul_ball,
-        parse_strikeout,
-        parse_strike,
-        parse_ground_out,
-        parse_flyout,
-        parse_inning_end,
-        parse_hit,
-        parse_double_play,
+        alt((
+            parse_empty,
+            parse_lets_go,
+            parse_play_ball,
+            parse_inning_turnover,
+            parse_batter_up,
+            parse_inning_end,
+            parse_pitcher_change,
+            parse_game_over,
+        )),
+        alt((
+            parse_ball,
+            parse_foul_ball,
+            parse_strikeout,
+            parse_strike,
+            parse_walk,
+            parse_hit_by_pitch,
+            parse_mild_pitch,
+        )),
+        alt((
+            parse_ground_out,
+            parse_flyout,
+            parse_sacrifice_fly,
+            parse_hit,
+            parse_home_run,
+            parse_double_play,
+            parse_fielders_choice,
+        )),
+        alt((parse_stolen_base, parse_caught_stealing)),
+        alt((
+            parse_incineration,
+            parse_peanut,
+            parse_feedback_swap,
+            parse_reverb_shuffle,
+            parse_blooddrain,
+            parse_birds_flavor,
+            parse_party,
+        )),
     ))
     .parse(input)
 }
@@ -70,6 +239,12 @@ fn parse_empty(input: &str) -> ParserResult<ParsedUpdateData> {
     eof.map(|_| ParsedUpdateData::Empty).parse(input)
 }
 
+fn parse_lets_go(input: &str) -> ParserResult<ParsedUpdateData> {
+    tag("Let's Go!")
+        .map(|_| ParsedUpdateData::LetsGo)
+        .parse(input)
+}
+
 fn parse_play_ball(input: &str) -> ParserResult<ParsedUpdateData> {
     tag("Play ball!")
         .map(|_| ParsedUpdateData::PlayBall)
@@ -114,8 +289,10 @@ fn parse_foul_ball(input: &str) -> ParserResult<ParsedUpdateData> {
 
 fn parse_strikeout(input: &str) -> ParserResult<ParsedUpdateData> {
     alt((
-        parse_terminated(" strikes out looking.").map(|_| ParsedUpdateData::StrikeoutLooking),
-        parse_terminated(" strikes out swinging.").map(|_| ParsedUpdateData::StrikeoutSwinging),
+        parse_terminated(" strikes out looking.")
+            .map(|batter| ParsedUpdateData::StrikeoutLooking { batter }),
+        parse_terminated(" strikes out swinging.")
+            .map(|batter| ParsedUpdateData::StrikeoutSwinging { batter }),
     ))
     .parse(input)
 }
@@ -135,20 +312,97 @@ fn parse_strike(input: &str) -> ParserResult<ParsedUpdateData> {
     Ok((input, strike_type))
 }
 
+enum SacrificeAdvance<'u> {
+    Advanced(&'u str),
+    Scored(&'u str),
+}
+
+fn parse_sacrifice_advance(input: &str) -> ParserResult<SacrificeAdvance> {
+    let (input, _) = tag("\n").parse(input)?;
+    alt((
+        parse_terminated(" tags up and scores!").map(SacrificeAdvance::Scored),
+        parse_terminated(" scores on the sacrifice.").map(SacrificeAdvance::Scored),
+        parse_terminated(" scores!").map(SacrificeAdvance::Scored),
+        parse_terminated(" tags up to ")
+            .and(parse_terminated("!"))
+            .map(|(name, _)| SacrificeAdvance::Advanced(name)),
+        parse_terminated(" advances on the sacrifice.").map(SacrificeAdvance::Advanced),
+    ))
+    .parse(input)
+}
+
+fn split_advances<'u>(advances: Vec<SacrificeAdvance<'u>>) -> (Vec<&'u str>, Vec<&'u str>) {
+    let mut advanced = Vec::new();
+    let mut scored = Vec::new();
+    for advance in advances {
+        match advance {
+            SacrificeAdvance::Advanced(name) => advanced.push(name),
+            SacrificeAdvance::Scored(name) => scored.push(name),
+        }
+    }
+    (advanced, scored)
+}
+
+// Plain outs become sacrifices if any runners moved up on them
+fn out_or_sacrifice<'u>(
+    batter: &'u str,
+    fielder: &'u str,
+    is_flyout: bool,
+    advances: Vec<SacrificeAdvance<'u>>,
+) -> ParsedUpdateData<'u> {
+    if advances.is_empty() {
+        return if is_flyout {
+            ParsedUpdateData::Flyout { batter, fielder }
+        } else {
+            ParsedUpdateData::GroundOut { batter, fielder }
+        };
+    }
+
+    let (advanced, scored) = split_advances(advances);
+    ParsedUpdateData::Sacrifice {
+        batter,
+        fielder,
+        is_flyout,
+        advanced,
+        scored,
+    }
+}
+
 fn parse_ground_out(input: &str) -> ParserResult<ParsedUpdateData> {
-    let (input, _batter_name) = parse_terminated(" hit a ground out to ").parse(input)?;
+    let (input, batter) = parse_terminated(" hit a ground out to ").parse(input)?;
     // TODO Parsing just a period is fragile; try porting parse_until_period_eof from Fed
-    let (input, _fielder_name) = parse_terminated(".").parse(input)?;
+    let (input, fielder) = parse_terminated(".").parse(input)?;
+    let (input, advances) = many0(parse_sacrifice_advance).parse(input)?;
 
-    Ok((input, ParsedUpdateData::GroundOut))
+    Ok((input, out_or_sacrifice(batter, fielder, false, advances)))
 }
 
 fn parse_flyout(input: &str) -> ParserResult<ParsedUpdateData> {
-    let (input, _batter_name) = parse_terminated(" hit a flyout to ").parse(input)?;
+    let (input, batter) = parse_terminated(" hit a flyout to ").parse(input)?;
     // TODO Parsing just a period is fragile; try porting parse_until_period_eof from Fed
-    let (input, _fielder_name) = parse_terminated(".").parse(input)?;
+    let (input, fielder) = parse_terminated(".").parse(input)?;
+    let (input, advances) = many0(parse_sacrifice_advance).parse(input)?;
+
+    Ok((input, out_or_sacrifice(batter, fielder, true, advances)))
+}
+
+fn parse_sacrifice_fly(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, batter) = parse_terminated(" hit a sacrifice fly to ").parse(input)?;
+    let (input, fielder) = parse_terminated(".").parse(input)?;
+    let (input, advances) = many0(parse_sacrifice_advance).parse(input)?;
+
+    let (advanced, scored) = split_advances(advances);
 
-    Ok((input, ParsedUpdateData::Flyout))
+    Ok((
+        input,
+        ParsedUpdateData::Sacrifice {
+            batter,
+            fielder,
+            is_flyout: true,
+            advanced,
+            scored,
+        },
+    ))
 }
 
 fn parse_inning_end(input: &str) -> ParserResult<ParsedUpdateData> {
@@ -167,7 +421,7 @@ fn parse_score(input: &str) -> ParserResult<&str> {
 }
 
 fn parse_hit(input: &str) -> ParserResult<ParsedUpdateData> {
-    let (input, _batter_name) = parse_terminated(" hits a ").parse(input)?;
+    let (input, batter) = parse_terminated(" hits a ").parse(input)?;
     let (input, bases) = alt((
         tag("Single").map(|_| 1),
         tag("Double").map(|_| 2),
@@ -179,13 +433,449 @@ fn parse_hit(input: &str) -> ParserResult<ParsedUpdateData> {
     
     let (input, scored) = many0(parse_score).parse(input)?;
 
-    Ok((input, ParsedUpdateData::Hit { bases, scored }))
+    Ok((input, ParsedUpdateData::Hit { batter, bases, scored }))
 }
 
 fn parse_double_play(input: &str) -> ParserResult<ParsedUpdateData> {
-    // This assumes there's always a score which I don't think is the case
-    let (input, _batter_name) = parse_terminated(" hit into a double play!\n").parse(input)?;
-    let (input, _runner_name) = parse_terminated(" scores!").parse(input)?;
+    let (input, batter) = parse_terminated(" hit into a double play!").parse(input)?;
+    let (input, scored) = many0(parse_score).parse(input)?;
+
+    Ok((input, ParsedUpdateData::DoublePlay { batter, scored }))
+}
+
+fn parse_home_run(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, batter) = parse_terminated(" hits a ").parse(input)?;
+    let (input, runs) = alt((
+        tag("solo home run!").map(|_| 1),
+        parse_whole_number
+            .and(tag("-run home run!"))
+            .map(|(runs, _)| runs),
+        tag("grand slam!").map(|_| 4),
+    ))
+    .parse(input)?;
+
+    Ok((input, ParsedUpdateData::HomeRun { batter, runs }))
+}
+
+fn parse_walk(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, batter) = parse_terminated(" draws a walk.").parse(input)?;
+    let (input, scored) = many0(parse_score).parse(input)?;
+
+    Ok((input, ParsedUpdateData::Walk { batter, scored }))
+}
+
+// Returns the base number, using the same numbering as `bases_occupied`
+fn parse_base_name(input: &str) -> ParserResult<i64> {
+    alt((
+        tag("first base").map(|_| 0),
+        tag("second base").map(|_| 1),
+        tag("third base").map(|_| 2),
+        tag("fourth base").map(|_| 3),
+        // Home is fourth base until fifth base exists
+        tag("home").map(|_| 3),
+    ))
+    .parse(input)
+}
+
+fn parse_stolen_base(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, runner) = parse_terminated(" steals ").parse(input)?;
+    let (input, base) = parse_base_name.parse(input)?;
+    let (input, _) = tag("!").parse(input)?;
+
+    Ok((input, ParsedUpdateData::StolenBase { runner, base }))
+}
+
+fn parse_caught_stealing(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, runner) = parse_terminated(" gets caught stealing ").parse(input)?;
+    let (input, base) = parse_base_name.parse(input)?;
+    let (input, _) = tag(".").parse(input)?;
+
+    Ok((input, ParsedUpdateData::CaughtStealing { runner, base }))
+}
+
+fn parse_fielders_choice(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, runner_out) = parse_terminated(" out at ").parse(input)?;
+    let (input, base) = parse_base_name.parse(input)?;
+    let (input, _) = tag(".").parse(input)?;
+    let (input, mut scored) = many0(parse_score).parse(input)?;
+    let (input, _) = tag("\n").parse(input)?;
+    let (input, batter) = parse_terminated(" reaches on fielder's choice.").parse(input)?;
+    let (input, scored_after) = many0(parse_score).parse(input)?;
+    scored.extend(scored_after);
+
+    Ok((
+        input,
+        ParsedUpdateData::FieldersChoice {
+            batter,
+            runner_out,
+            base,
+            scored,
+        },
+    ))
+}
+
+fn parse_hit_by_pitch(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _pitcher_name) = parse_terminated(" hits ").parse(input)?;
+    let (input, batter) = parse_terminated(" with a pitch!").parse(input)?;
+    let (input, scored) = many0(parse_score).parse(input)?;
+
+    Ok((input, ParsedUpdateData::HitByPitch { batter, scored }))
+}
+
+fn parse_mild_pitch(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _pitcher_name) = parse_terminated(" throws a Mild pitch!\n").parse(input)?;
+    let (input, is_walk) = alt((
+        parse_terminated(" draws a walk.").map(|_| true),
+        (
+            tag("Ball, "),
+            parse_whole_number,
+            tag("-"),
+            parse_whole_number,
+            tag("."),
+        )
+            .map(|_| false),
+    ))
+    .parse(input)?;
+    let (input, runners_advanced) = opt(tag("\nRunners advance on the pathetic play!"))
+        .map(|advanced| advanced.is_some())
+        .parse(input)?;
+    let (input, scored) = many0(parse_score).parse(input)?;
+
+    Ok((
+        input,
+        ParsedUpdateData::MildPitch {
+            is_walk,
+            runners_advanced,
+            scored,
+        },
+    ))
+}
+
+fn parse_pitcher_change(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, pitcher) = parse_terminated(" is now pitching for the ").parse(input)?;
+    let (input, _team_name) = parse_terminated(".").parse(input)?;
+
+    Ok((input, ParsedUpdateData::PitcherChange { pitcher }))
+}
+
+fn parse_game_over(input: &str) -> ParserResult<ParsedUpdateData> {
+    tag("Game over.")
+        .map(|_| ParsedUpdateData::GameOver)
+        .parse(input)
+}
+
+fn parse_attribute(input: &str) -> ParserResult<Attribute> {
+    alt((
+        alt((
+            tag("Pressurization").map(|_| Attribute::Pressurization),
+            tag("Cinnamon").map(|_| Attribute::Cinnamon),
+            tag("Buoyancy").map(|_| Attribute::Buoyancy),
+            tag("Divinity").map(|_| Attribute::Divinity),
+            tag("Martyrdom").map(|_| Attribute::Martyrdom),
+            tag("Moxie").map(|_| Attribute::Moxie),
+            tag("Musclitude").map(|_| Attribute::Musclitude),
+            tag("Patheticism").map(|_| Attribute::Patheticism),
+            tag("Thwackability").map(|_| Attribute::Thwackability),
+            tag("Tragicness").map(|_| Attribute::Tragicness),
+            tag("Ruthlessness").map(|_| Attribute::Ruthlessness),
+            tag("Overpowerment").map(|_| Attribute::Overpowerment),
+            tag("Unthwackability").map(|_| Attribute::Unthwackability),
+            tag("Shakespearianism").map(|_| Attribute::Shakespearianism),
+            tag("Suppression").map(|_| Attribute::Suppression),
+            tag("Coldness").map(|_| Attribute::Coldness),
+        )),
+        alt((
+            tag("Base Thirst").map(|_| Attribute::BaseThirst),
+            tag("Laserlikeness").map(|_| Attribute::Laserlikeness),
+            tag("Continuation").map(|_| Attribute::Continuation),
+            tag("Ground Friction").map(|_| Attribute::GroundFriction),
+            tag("Indulgence").map(|_| Attribute::Indulgence),
+            tag("Anticapitalism").map(|_| Attribute::Anticapitalism),
+            tag("Chasiness").map(|_| Attribute::Chasiness),
+            tag("Omniscience").map(|_| Attribute::Omniscience),
+            tag("Tenaciousness").map(|_| Attribute::Tenaciousness),
+            tag("Watchfulness").map(|_| Attribute::Watchfulness),
+        )),
+    ))
+    .parse(input)
+}
+
+// "Their Moxie, Divinity and Base Thirst are boosted."
+fn parse_party_boost(input: &str) -> ParserResult<Vec<Attribute>> {
+    let (input, _) = tag("\nTheir ").parse(input)?;
+    let (input, boosted) =
+        separated_list1(alt((tag(", "), tag(" and "))), parse_attribute).parse(input)?;
+    let (input, _) = alt((tag(" are boosted."), tag(" is boosted."))).parse(input)?;
+
+    Ok((input, boosted))
+}
+
+fn parse_party(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, player) = parse_terminated(" is Partying!").parse(input)?;
+    let (input, boosted) = opt(parse_party_boost).parse(input)?;
+
+    Ok((
+        input,
+        ParsedUpdateData::Party {
+            player,
+            boosted: boosted.unwrap_or_default(),
+        },
+    ))
+}
+
+fn parse_incineration(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _) = tag("Rogue Umpire incinerated ").parse(input)?;
+    let (input, victim) = parse_terminated("!\nReplaced by ").parse(input)?;
+    let (input, replacement) = take_till1(|c| c == '\n').parse(input)?;
+
+    Ok((input, ParsedUpdateData::Incineration { victim, replacement }))
+}
+
+fn parse_peanut(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, player) = parse_terminated(" swallowed a stray peanut and had a").parse(input)?;
+    let (input, allergic) = alt((
+        tag("n allergic reaction!").map(|_| true),
+        tag(" yummy reaction!").map(|_| false),
+    ))
+    .parse(input)?;
+
+    Ok((input, ParsedUpdateData::Peanut { player, allergic }))
+}
+
+fn parse_feedback_swap(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _) = tag("Reality flickered. ").parse(input)?;
+    let (input, player_a) = parse_terminated(" and ").parse(input)?;
+    let (input, player_b) = parse_terminated(" switched teams in the feedback!").parse(input)?;
+
+    Ok((input, ParsedUpdateData::FeedbackSwap { player_a, player_b }))
+}
+
+fn parse_reverb_shuffle(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _) = tag("Reverberations are at dangerous levels!\nThe ").parse(input)?;
+    let (input, (team, shuffled)) = alt((
+        parse_terminated(" were shuffled in the Reverb!")
+            .map(|team| (team, ReverbShuffled::WholeTeam)),
+        parse_terminated(" had several players shuffled in the Reverb!")
+            .map(|team| (team, ReverbShuffled::SeveralPlayers)),
+        parse_terminated(" had their rotation shuffled in the Reverb!")
+            .map(|team| (team, ReverbShuffled::Rotation)),
+        parse_terminated(" had their lineup shuffled in the Reverb!")
+            .map(|team| (team, ReverbShuffled::Lineup)),
+    ))
+    .parse(input)?;
+
+    Ok((input, ParsedUpdateData::ReverbShuffle { team, shuffled }))
+}
+
+fn parse_blooddrain(input: &str) -> ParserResult<ParsedUpdateData> {
+    let (input, _) = tag("The Blooddrain gurgled!\n").parse(input)?;
+    let (input, siphoner) = parse_terminated(" siphoned some of ").parse(input)?;
+    let (input, victim) = parse_terminated("'s ").parse(input)?;
+    let (input, ability) = alt((
+        tag("hitting").map(|_| SiphonedAbility::Hitting),
+        tag("pitching").map(|_| SiphonedAbility::Pitching),
+        tag("baserunning").map(|_| SiphonedAbility::Baserunning),
+        tag("defensive").map(|_| SiphonedAbility::Defensive),
+    ))
+    .parse(input)?;
+    let (input, _) = tag(" ability!").parse(input)?;
+
+    Ok((
+        input,
+        ParsedUpdateData::Blooddrain {
+            siphoner,
+            victim,
+            ability,
+        },
+    ))
+}
+
+fn parse_birds_flavor(input: &str) -> ParserResult<ParsedUpdateData> {
+    alt((
+        tag("The birds continue to stare."),
+        tag("The birds are very loud!"),
+        tag("The birds are mad at you."),
+        tag("The birds are paying attention."),
+        tag("The birds are after the donuts."),
+        tag("The birds are chirping."),
+        tag("Several birds are pecking..."),
+        tag("This is too many birds."),
+        tag("What are we gonna do with all these birds?"),
+        tag("Oh dear Gods..."),
+        tag("I hardly think a few birds are going to bring about the end of the world."),
+        tag("Birds circle... but they don't find what they're looking for."),
+    ))
+    .map(|_| ParsedUpdateData::BirdsFlavor)
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(description: &str) -> ParsedUpdateData {
+        let (rest, data) = parse_description(description).expect("Description should parse");
+        assert_eq!(rest, "", "Description should be fully consumed");
+        data
+    }
+
+    #[test]
+    fn leftover_text_is_unparsed() {
+        assert!(parse_whole_description("Ball. 1-0").is_ok());
+        assert!(parse_whole_description("Ball. 1-0\nSomething we've never seen before").is_err());
+    }
+
+    #[test]
+    fn home_runs() {
+        assert!(matches!(
+            parse("Wyatt Mason hits a solo home run!"),
+            ParsedUpdateData::HomeRun { runs: 1, .. }
+        ));
+        assert!(matches!(
+            parse("Wyatt Mason hits a 3-run home run!"),
+            ParsedUpdateData::HomeRun { runs: 3, .. }
+        ));
+        assert!(matches!(
+            parse("Wyatt Mason hits a grand slam!"),
+            ParsedUpdateData::HomeRun { runs: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn steals() {
+        assert!(matches!(
+            parse("Kaj Statter Jr. steals third base!"),
+            ParsedUpdateData::StolenBase { runner: "Kaj Statter Jr.", base: 2 }
+        ));
+        assert!(matches!(
+            parse("Kaj Statter Jr. gets caught stealing home."),
+            ParsedUpdateData::CaughtStealing { runner: "Kaj Statter Jr.", base: 3 }
+        ));
+    }
+
+    #[test]
+    fn sacrifices() {
+        let ParsedUpdateData::Sacrifice {
+            is_flyout,
+            advanced,
+            scored,
+            ..
+        } = parse("Nagomi Mcdaniel hit a flyout to York Silk.\nJessica Telephone tags up and scores!")
+        else {
+            panic!("Expected a sacrifice");
+        };
+        assert!(is_flyout);
+        assert!(advanced.is_empty());
+        assert_eq!(scored, vec!["Jessica Telephone"]);
+
+        assert!(matches!(
+            parse("Nagomi Mcdaniel hit a ground out to York Silk."),
+            ParsedUpdateData::GroundOut {
+                batter: "Nagomi Mcdaniel",
+                fielder: "York Silk"
+            }
+        ));
+    }
+
+    #[test]
+    fn double_plays() {
+        assert!(matches!(
+            parse("Nagomi Mcdaniel hit into a double play!"),
+            ParsedUpdateData::DoublePlay { batter: "Nagomi Mcdaniel", ref scored } if scored.is_empty()
+        ));
+
+        let ParsedUpdateData::DoublePlay { scored, .. } =
+            parse("Nagomi Mcdaniel hit into a double play!\nJessica Telephone scores!")
+        else {
+            panic!("Expected a double play");
+        };
+        assert_eq!(scored, vec!["Jessica Telephone"]);
+    }
+
+    #[test]
+    fn fielders_choice() {
+        let ParsedUpdateData::FieldersChoice {
+            runner_out, base, ..
+        } = parse("Jessica Telephone out at second base.\nNagomi Mcdaniel reaches on fielder's choice.")
+        else {
+            panic!("Expected a fielder's choice");
+        };
+        assert_eq!(runner_out, "Jessica Telephone");
+        assert_eq!(base, 1);
+    }
+
+    #[test]
+    fn mild_pitch() {
+        assert!(matches!(
+            parse("Jaylen Hotdogfingers throws a Mild pitch!\nBall, 2-1.\nRunners advance on the pathetic play!"),
+            ParsedUpdateData::MildPitch { is_walk: false, runners_advanced: true, .. }
+        ));
+    }
+
+    #[test]
+    fn party() {
+        let ParsedUpdateData::Party { player, boosted } = parse("Jessica Telephone is Partying!")
+        else {
+            panic!("Expected a party");
+        };
+        assert_eq!(player, "Jessica Telephone");
+        assert!(boosted.is_empty());
+
+        let ParsedUpdateData::Party { boosted, .. } = parse(
+            "Jessica Telephone is Partying!\nTheir Moxie, Base Thirst and Watchfulness are boosted.",
+        ) else {
+            panic!("Expected a party");
+        };
+        assert_eq!(
+            boosted,
+            [Attribute::Moxie, Attribute::BaseThirst, Attribute::Watchfulness]
+        );
+    }
+
+    #[test]
+    fn walks_and_game_over() {
+        let ParsedUpdateData::Walk { scored, .. } =
+            parse("Nagomi Mcdaniel draws a walk.\nJessica Telephone scores!")
+        else {
+            panic!("Expected a walk");
+        };
+        assert_eq!(scored, vec!["Jessica Telephone"]);
+
+        assert!(matches!(parse("Game over."), ParsedUpdateData::GameOver));
+    }
 
-    Ok((input, ParsedUpdateData::DoublePlay))
+    #[test]
+    fn weather_events() {
+        assert!(matches!(
+            parse("Rogue Umpire incinerated Hades Tigers hitter Sixpack Dogwalker!\nReplaced by Nerd Pacheco"),
+            ParsedUpdateData::Incineration {
+                victim: "Hades Tigers hitter Sixpack Dogwalker",
+                replacement: "Nerd Pacheco",
+            }
+        ));
+        assert!(matches!(
+            parse("Reverberations are at dangerous levels!\nThe Hades Tigers had their lineup shuffled in the Reverb!"),
+            ParsedUpdateData::ReverbShuffle { team: "Hades Tigers", shuffled: ReverbShuffled::Lineup }
+        ));
+        assert!(matches!(
+            parse("Reverberations are at dangerous levels!\nThe Hades Tigers were shuffled in the Reverb!"),
+            ParsedUpdateData::ReverbShuffle { team: "Hades Tigers", shuffled: ReverbShuffled::WholeTeam }
+        ));
+        assert!(matches!(
+            parse("The Blooddrain gurgled!\nYork Silk siphoned some of Nagomi Mcdaniel's defensive ability!"),
+            ParsedUpdateData::Blooddrain {
+                siphoner: "York Silk",
+                victim: "Nagomi Mcdaniel",
+                ability: SiphonedAbility::Defensive,
+            }
+        ));
+        assert!(matches!(
+            parse("Nagomi Mcdaniel swallowed a stray peanut and had an allergic reaction!"),
+            ParsedUpdateData::Peanut { player: "Nagomi Mcdaniel", allergic: true }
+        ));
+        assert!(matches!(
+            parse("Reality flickered. York Silk and Nagomi Mcdaniel switched teams in the feedback!"),
+            ParsedUpdateData::FeedbackSwap { player_a: "York Silk", player_b: "Nagomi Mcdaniel" }
+        ));
+    }
 }